@@ -11,12 +11,22 @@ const VERSION: &str = "2017-12-14";
 #[derive(Clone)]
 pub struct BillingService {
     client: Client,
+    form_body: bool,
 }
 
 #[cfg(feature = "async")]
 impl BillingService {
     pub(crate) fn new(client: Client) -> Self {
-        Self { client }
+        Self {
+            client,
+            form_body: false,
+        }
+    }
+
+    /// Send calls as `POST` with a form body instead of a query string.
+    pub fn form_body(mut self) -> Self {
+        self.form_body = true;
+        self
     }
 
     pub async fn query_account_balance(
@@ -29,6 +39,7 @@ impl BillingService {
                 "QueryAccountBalance",
                 VERSION,
                 params.into_query(),
+                self.form_body,
             )
             .await
     }
@@ -38,12 +49,22 @@ impl BillingService {
 #[derive(Clone)]
 pub struct BlockingBillingService {
     client: BlockingClient,
+    form_body: bool,
 }
 
 #[cfg(feature = "blocking")]
 impl BlockingBillingService {
     pub(crate) fn new(client: BlockingClient) -> Self {
-        Self { client }
+        Self {
+            client,
+            form_body: false,
+        }
+    }
+
+    /// Send calls as `POST` with a form body instead of a query string.
+    pub fn form_body(mut self) -> Self {
+        self.form_body = true;
+        self
     }
 
     pub fn query_account_balance(
@@ -55,6 +76,7 @@ impl BlockingBillingService {
             "QueryAccountBalance",
             VERSION,
             params.into_query(),
+            self.form_body,
         )
     }
 }
@@ -21,12 +21,25 @@ const VERSION: &str = "2014-05-26";
 #[derive(Clone)]
 pub struct EcsService {
     client: Client,
+    form_body: bool,
 }
 
 #[cfg(feature = "async")]
 impl EcsService {
     pub(crate) fn new(client: Client) -> Self {
-        Self { client }
+        Self {
+            client,
+            form_body: false,
+        }
+    }
+
+    /// Send calls as `POST` with the parameters in an `application/x-www-form-urlencoded`
+    /// body, e.g. `RunInstances` with a large `UserData`.
+    ///
+    /// Calls whose parameters are too long for a query string are sent this way anyway.
+    pub fn form_body(mut self) -> Self {
+        self.form_body = true;
+        self
     }
 
     async fn rpc_json_value(
@@ -35,7 +48,13 @@ impl EcsService {
         params: BTreeMap<String, String>,
     ) -> Result<serde_json::Value, Error> {
         self.client
-            .rpc_json(self.client.endpoint_ecs(), action, VERSION, params)
+            .rpc_json(
+                self.client.endpoint_ecs(),
+                action,
+                VERSION,
+                params,
+                self.form_body,
+            )
             .await
     }
 
@@ -148,12 +167,25 @@ impl EcsService {
 #[derive(Clone)]
 pub struct BlockingEcsService {
     client: BlockingClient,
+    form_body: bool,
 }
 
 #[cfg(feature = "blocking")]
 impl BlockingEcsService {
     pub(crate) fn new(client: BlockingClient) -> Self {
-        Self { client }
+        Self {
+            client,
+            form_body: false,
+        }
+    }
+
+    /// Send calls as `POST` with the parameters in an `application/x-www-form-urlencoded`
+    /// body, e.g. `RunInstances` with a large `UserData`.
+    ///
+    /// Calls whose parameters are too long for a query string are sent this way anyway.
+    pub fn form_body(mut self) -> Self {
+        self.form_body = true;
+        self
     }
 
     fn rpc_json_value(
@@ -161,8 +193,13 @@ impl BlockingEcsService {
         action: &'static str,
         params: BTreeMap<String, String>,
     ) -> Result<serde_json::Value, Error> {
-        self.client
-            .rpc_json(self.client.endpoint_ecs(), action, VERSION, params)
+        self.client.rpc_json(
+            self.client.endpoint_ecs(),
+            action,
+            VERSION,
+            params,
+            self.form_body,
+        )
     }
 
     pub fn describe_regions(
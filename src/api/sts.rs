@@ -1,11 +1,18 @@
 use std::collections::BTreeMap;
 
-use crate::{error::Error, types::sts::CallerIdentity};
+use crate::{
+    error::Error,
+    types::sts::{
+        AssumeRoleParams, AssumeRoleResponse, AssumeRoleWithOidcParams, AssumeRoleWithOidcResponse,
+        AssumeRoleWithSamlParams, AssumeRoleWithSamlResponse, CallerIdentity,
+    },
+};
 
-#[cfg(feature = "blocking")]
-use crate::client::BlockingClient;
+use crate::auth::Auth;
 #[cfg(feature = "async")]
-use crate::client::Client;
+use crate::{auth::AssumeRoleProvider, client::Client};
+#[cfg(feature = "blocking")]
+use crate::{auth::BlockingAssumeRoleProvider, client::BlockingClient};
 
 const VERSION: &str = "2015-04-01";
 
@@ -13,12 +20,23 @@ const VERSION: &str = "2015-04-01";
 #[derive(Clone)]
 pub struct StsService {
     client: Client,
+    form_body: bool,
 }
 
 #[cfg(feature = "async")]
 impl StsService {
     pub(crate) fn new(client: Client) -> Self {
-        Self { client }
+        Self {
+            client,
+            form_body: false,
+        }
+    }
+
+    /// Send calls as `POST` with a form body instead of a query string, e.g. for an
+    /// `AssumeRole` with a long inline policy (done automatically past a few KiB).
+    pub fn form_body(mut self) -> Self {
+        self.form_body = true;
+        self
     }
 
     pub async fn get_caller_identity(&self) -> Result<CallerIdentity, Error> {
@@ -28,6 +46,102 @@ impl StsService {
                 "GetCallerIdentity",
                 VERSION,
                 BTreeMap::new(),
+                self.form_body,
+            )
+            .await
+    }
+
+    pub async fn assume_role(&self, params: AssumeRoleParams) -> Result<AssumeRoleResponse, Error> {
+        self.client
+            .rpc_json(
+                self.client.endpoint_sts(),
+                "AssumeRole",
+                VERSION,
+                params.into_query(),
+                self.form_body,
+            )
+            .await
+    }
+
+    /// A credentials provider that assumes the role with this client's credentials and
+    /// assumes it again shortly before the session expires.
+    pub fn assume_role_provider(&self, params: AssumeRoleParams) -> AssumeRoleProvider {
+        AssumeRoleProvider::new(self.clone(), params)
+    }
+
+    /// A credentials provider for a chain of roles: the first role is assumed with this
+    /// client's credentials and every next role with the previous role's session, each
+    /// with its own session name and policy. Every session is refreshed shortly before it
+    /// expires.
+    pub fn role_chain_provider(
+        &self,
+        roles: impl IntoIterator<Item = AssumeRoleParams>,
+    ) -> Result<AssumeRoleProvider, Error> {
+        let mut roles = roles.into_iter();
+        let first = roles
+            .next()
+            .ok_or_else(|| Error::invalid_config("role chain needs at least one role", None))?;
+        Ok(
+            roles.fold(self.assume_role_provider(first), |provider, params| {
+                self.client
+                    .with_auth(Auth::provider(provider))
+                    .sts()
+                    .assume_role_provider(params)
+            }),
+        )
+    }
+
+    /// One client per role (e.g. per target account), each signing with a session of its
+    /// role assumed with this client's credentials. The clients share this client's
+    /// endpoints, settings and transport.
+    pub fn assume_role_clients(
+        &self,
+        roles: impl IntoIterator<Item = AssumeRoleParams>,
+    ) -> Vec<Client> {
+        roles
+            .into_iter()
+            .map(|params| {
+                self.client
+                    .with_auth(Auth::provider(self.assume_role_provider(params)))
+            })
+            .collect()
+    }
+
+    /// Exchange an OIDC token (e.g. an ACK RRSA service account token) for STS credentials.
+    ///
+    /// The call is not signed, so the client needs no credentials. The token is always sent
+    /// in a `POST` form body, never in the URL.
+    pub async fn assume_role_with_oidc(
+        &self,
+        params: AssumeRoleWithOidcParams,
+    ) -> Result<AssumeRoleWithOidcResponse, Error> {
+        self.client
+            .rpc_json_anonymous(
+                self.client.endpoint_sts(),
+                "AssumeRoleWithOIDC",
+                VERSION,
+                params.into_query(),
+                true,
+            )
+            .await
+    }
+
+    /// Exchange a SAML assertion from the IdP for STS credentials.
+    ///
+    /// The call is not signed, so the client needs no credentials. The assertion is always
+    /// sent in a `POST` form body, never in the URL, and is redacted from `Debug` output and
+    /// error body snippets.
+    pub async fn assume_role_with_saml(
+        &self,
+        params: AssumeRoleWithSamlParams,
+    ) -> Result<AssumeRoleWithSamlResponse, Error> {
+        self.client
+            .rpc_json_anonymous(
+                self.client.endpoint_sts(),
+                "AssumeRoleWithSAML",
+                VERSION,
+                params.into_query(),
+                true,
             )
             .await
     }
@@ -37,12 +151,23 @@ impl StsService {
 #[derive(Clone)]
 pub struct BlockingStsService {
     client: BlockingClient,
+    form_body: bool,
 }
 
 #[cfg(feature = "blocking")]
 impl BlockingStsService {
     pub(crate) fn new(client: BlockingClient) -> Self {
-        Self { client }
+        Self {
+            client,
+            form_body: false,
+        }
+    }
+
+    /// Send calls as `POST` with a form body instead of a query string, e.g. for an
+    /// `AssumeRole` with a long inline policy (done automatically past a few KiB).
+    pub fn form_body(mut self) -> Self {
+        self.form_body = true;
+        self
     }
 
     pub fn get_caller_identity(&self) -> Result<CallerIdentity, Error> {
@@ -51,6 +176,96 @@ impl BlockingStsService {
             "GetCallerIdentity",
             VERSION,
             BTreeMap::new(),
+            self.form_body,
+        )
+    }
+
+    pub fn assume_role(&self, params: AssumeRoleParams) -> Result<AssumeRoleResponse, Error> {
+        self.client.rpc_json(
+            self.client.endpoint_sts(),
+            "AssumeRole",
+            VERSION,
+            params.into_query(),
+            self.form_body,
+        )
+    }
+
+    /// A credentials provider that assumes the role with this client's credentials and
+    /// assumes it again shortly before the session expires.
+    pub fn assume_role_provider(&self, params: AssumeRoleParams) -> BlockingAssumeRoleProvider {
+        BlockingAssumeRoleProvider::new(self.clone(), params)
+    }
+
+    /// A credentials provider for a chain of roles: the first role is assumed with this
+    /// client's credentials and every next role with the previous role's session, each
+    /// with its own session name and policy. Every session is refreshed shortly before it
+    /// expires.
+    pub fn role_chain_provider(
+        &self,
+        roles: impl IntoIterator<Item = AssumeRoleParams>,
+    ) -> Result<BlockingAssumeRoleProvider, Error> {
+        let mut roles = roles.into_iter();
+        let first = roles
+            .next()
+            .ok_or_else(|| Error::invalid_config("role chain needs at least one role", None))?;
+        Ok(
+            roles.fold(self.assume_role_provider(first), |provider, params| {
+                self.client
+                    .with_auth(Auth::blocking_provider(provider))
+                    .sts()
+                    .assume_role_provider(params)
+            }),
+        )
+    }
+
+    /// One client per role (e.g. per target account), each signing with a session of its
+    /// role assumed with this client's credentials. The clients share this client's
+    /// endpoints, settings and transport.
+    pub fn assume_role_clients(
+        &self,
+        roles: impl IntoIterator<Item = AssumeRoleParams>,
+    ) -> Vec<BlockingClient> {
+        roles
+            .into_iter()
+            .map(|params| {
+                self.client
+                    .with_auth(Auth::blocking_provider(self.assume_role_provider(params)))
+            })
+            .collect()
+    }
+
+    /// Exchange an OIDC token (e.g. an ACK RRSA service account token) for STS credentials.
+    ///
+    /// The call is not signed, so the client needs no credentials. The token is always sent
+    /// in a `POST` form body, never in the URL.
+    pub fn assume_role_with_oidc(
+        &self,
+        params: AssumeRoleWithOidcParams,
+    ) -> Result<AssumeRoleWithOidcResponse, Error> {
+        self.client.rpc_json_anonymous(
+            self.client.endpoint_sts(),
+            "AssumeRoleWithOIDC",
+            VERSION,
+            params.into_query(),
+            true,
+        )
+    }
+
+    /// Exchange a SAML assertion from the IdP for STS credentials.
+    ///
+    /// The call is not signed, so the client needs no credentials. The assertion is always
+    /// sent in a `POST` form body, never in the URL, and is redacted from `Debug` output and
+    /// error body snippets.
+    pub fn assume_role_with_saml(
+        &self,
+        params: AssumeRoleWithSamlParams,
+    ) -> Result<AssumeRoleWithSamlResponse, Error> {
+        self.client.rpc_json_anonymous(
+            self.client.endpoint_sts(),
+            "AssumeRoleWithSAML",
+            VERSION,
+            params.into_query(),
+            true,
         )
     }
 }
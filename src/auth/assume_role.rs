@@ -0,0 +1,154 @@
+use std::fmt;
+
+#[cfg(feature = "blocking")]
+use super::provider::BlockingCredentialsProvider;
+#[cfg(feature = "async")]
+use super::provider::{CredentialsFuture, CredentialsProvider};
+use super::{
+    Auth,
+    provider::{Credentials, CredentialsCache},
+    sts::StsClients,
+};
+use crate::{error::Error, types::sts::AssumeRoleParams};
+
+#[cfg(feature = "blocking")]
+use crate::api::BlockingStsService;
+#[cfg(feature = "async")]
+use crate::api::StsService;
+
+const PROVIDER_NAME: &str = "ram_role_arn";
+
+/// Credentials of an assumed RAM role, refreshed shortly before the session expires.
+///
+/// Created with [`StsService::assume_role_provider`]; the service's client signs the
+/// `AssumeRole` calls.
+#[cfg(feature = "async")]
+pub struct AssumeRoleProvider {
+    sts: StsService,
+    params: AssumeRoleParams,
+    cached: CredentialsCache,
+}
+
+#[cfg(feature = "async")]
+impl AssumeRoleProvider {
+    pub(crate) fn new(sts: StsService, params: AssumeRoleParams) -> Self {
+        Self {
+            sts,
+            params,
+            cached: CredentialsCache::new(),
+        }
+    }
+
+    async fn fetch(&self) -> Result<Credentials, Error> {
+        if let Some(credentials) = self.cached.fresh() {
+            return Ok(credentials);
+        }
+        let response = self.sts.assume_role(self.params.clone()).await?;
+        let credentials = Credentials::try_from(response.credentials)?;
+        Ok(self
+            .cached
+            .insert(credentials.with_provider_name(PROVIDER_NAME)))
+    }
+}
+
+#[cfg(feature = "async")]
+impl CredentialsProvider for AssumeRoleProvider {
+    fn provide_credentials(&self) -> CredentialsFuture<'_> {
+        Box::pin(self.fetch())
+    }
+
+    fn invalidate(&self) {
+        self.cached.clear();
+    }
+}
+
+#[cfg(feature = "async")]
+impl fmt::Debug for AssumeRoleProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AssumeRoleProvider")
+            .field("params", &self.params)
+            .finish()
+    }
+}
+
+/// Credentials of an assumed RAM role, refreshed shortly before the session expires.
+///
+/// Created with [`BlockingStsService::assume_role_provider`]; the service's client signs
+/// the `AssumeRole` calls.
+#[cfg(feature = "blocking")]
+pub struct BlockingAssumeRoleProvider {
+    sts: BlockingStsService,
+    params: AssumeRoleParams,
+    cached: CredentialsCache,
+}
+
+#[cfg(feature = "blocking")]
+impl BlockingAssumeRoleProvider {
+    pub(crate) fn new(sts: BlockingStsService, params: AssumeRoleParams) -> Self {
+        Self {
+            sts,
+            params,
+            cached: CredentialsCache::new(),
+        }
+    }
+
+    fn fetch(&self) -> Result<Credentials, Error> {
+        if let Some(credentials) = self.cached.fresh() {
+            return Ok(credentials);
+        }
+        let response = self.sts.assume_role(self.params.clone())?;
+        let credentials = Credentials::try_from(response.credentials)?;
+        Ok(self
+            .cached
+            .insert(credentials.with_provider_name(PROVIDER_NAME)))
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl BlockingCredentialsProvider for BlockingAssumeRoleProvider {
+    fn provide_credentials_blocking(&self) -> Result<Credentials, Error> {
+        self.fetch()
+    }
+
+    fn invalidate(&self) {
+        self.cached.clear();
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl fmt::Debug for BlockingAssumeRoleProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BlockingAssumeRoleProvider")
+            .field("params", &self.params)
+            .finish()
+    }
+}
+
+/// Assumes a RAM role from source credentials for built-in sources (CLI profiles), which
+/// serve both client flavours.
+#[derive(Debug)]
+pub(crate) struct RamRoleArnProvider {
+    params: AssumeRoleParams,
+    sts: StsClients,
+}
+
+impl RamRoleArnProvider {
+    pub(crate) fn new(source: Auth, params: AssumeRoleParams, sts_endpoint: String) -> Self {
+        Self {
+            params,
+            sts: StsClients::new(sts_endpoint, source),
+        }
+    }
+
+    #[cfg(feature = "async")]
+    pub(crate) async fn fetch(&self) -> Result<Credentials, Error> {
+        let response = self.sts.sts()?.assume_role(self.params.clone()).await?;
+        Ok(Credentials::try_from(response.credentials)?.with_provider_name(PROVIDER_NAME))
+    }
+
+    #[cfg(feature = "blocking")]
+    pub(crate) fn fetch_blocking(&self) -> Result<Credentials, Error> {
+        let response = self.sts.blocking_sts()?.assume_role(self.params.clone())?;
+        Ok(Credentials::try_from(response.credentials)?.with_provider_name(PROVIDER_NAME))
+    }
+}
@@ -0,0 +1,395 @@
+use std::{
+    sync::{Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+#[cfg(feature = "blocking")]
+use super::provider::BlockingCredentialsProvider;
+#[cfg(feature = "async")]
+use super::provider::{CredentialsFuture, CredentialsProvider};
+use super::{
+    cli_profile::CliProfileProvider,
+    ecs_ram_role::EcsRamRoleProvider,
+    env::{Env, EnvironmentProvider},
+    oidc::{OIDC_PROVIDER_ARN, OIDC_TOKEN_FILE, OidcRoleArnProvider, ROLE_ARN},
+    profile::ProfileFileProvider,
+    provider::Credentials,
+    uri::{CREDENTIALS_URI, CredentialsUriProvider},
+};
+use crate::{error::Error, types::RegionId};
+
+const CLI_PROFILE_DISABLED: &str = "ALIBABA_CLOUD_CLI_PROFILE_DISABLED";
+const ECS_METADATA_DISABLED: &str = "ALIBABA_CLOUD_ECS_METADATA_DISABLED";
+/// How long a lookup in which every source failed is reused before the sources are tried
+/// again.
+const FAILURE_BACKOFF: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Source {
+    Environment,
+    Oidc,
+    CliProfile,
+    Profile,
+    EcsRamRole,
+    CredentialsUri,
+}
+
+impl Source {
+    /// Lookup order of the official SDKs.
+    const ORDER: [Source; 6] = [
+        Source::Environment,
+        Source::Oidc,
+        Source::CliProfile,
+        Source::Profile,
+        Source::EcsRamRole,
+        Source::CredentialsUri,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Source::Environment => "environment",
+            Source::Oidc => "oidc_role_arn",
+            Source::CliProfile => "cli_profile",
+            Source::Profile => "profile",
+            Source::EcsRamRole => "ecs_ram_role",
+            Source::CredentialsUri => "credentials_uri",
+        }
+    }
+}
+
+/// The default credentials chain used by [`Auth::default_chain`](super::Auth::default_chain).
+///
+/// Sources are tried in the same order as the official SDKs:
+///
+/// 1. environment variables (`ALIBABA_CLOUD_ACCESS_KEY_ID`, ...),
+/// 2. OIDC role ARN (`ALIBABA_CLOUD_ROLE_ARN`, `ALIBABA_CLOUD_OIDC_PROVIDER_ARN`,
+///    `ALIBABA_CLOUD_OIDC_TOKEN_FILE`),
+/// 3. the `aliyun` CLI config (`~/.aliyun/config.json`, skipped when
+///    `ALIBABA_CLOUD_CLI_PROFILE_DISABLED=true`),
+/// 4. the shared credentials file (`~/.alibabacloud/credentials.ini`),
+/// 5. the ECS instance RAM role (skipped when `ALIBABA_CLOUD_ECS_METADATA_DISABLED=true`),
+/// 6. the credentials URI (`ALIBABA_CLOUD_CREDENTIALS_URI`).
+///
+/// The first source that yields credentials is remembered and used for later refreshes.
+/// When every source fails, the returned [`Error::Auth`] lists each source and why it
+/// was skipped; for the next 5 seconds that error is returned without trying the sources
+/// again, so requests on a machine without credentials do not each wait for the ECS
+/// metadata service.
+#[derive(Debug)]
+pub struct DefaultCredentialsChain {
+    environment: EnvironmentProvider,
+    oidc: Option<OidcRoleArnProvider>,
+    cli_profile: Option<CliProfileProvider>,
+    profile: ProfileFileProvider,
+    ecs_ram_role: Option<EcsRamRoleProvider>,
+    credentials_uri: Option<CredentialsUriProvider>,
+    selected: Mutex<Option<Source>>,
+    /// When the last lookup found no source, and its error message.
+    failure: Mutex<Option<(Instant, String)>>,
+}
+
+impl Default for DefaultCredentialsChain {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DefaultCredentialsChain {
+    pub fn new() -> Self {
+        Self::with_env(Env::real())
+    }
+
+    pub(crate) fn with_env(env: Env) -> Self {
+        let ecs_ram_role = if env.is_true(ECS_METADATA_DISABLED) {
+            None
+        } else {
+            Some(EcsRamRoleProvider::with_env(&env))
+        };
+
+        let cli_profile =
+            (!env.is_true(CLI_PROFILE_DISABLED)).then(|| CliProfileProvider::with_env(env.clone()));
+
+        let credentials_uri = CredentialsUriProvider::with_env(&env);
+
+        Self {
+            environment: EnvironmentProvider::with_env(env.clone()),
+            oidc: OidcRoleArnProvider::with_env(&env),
+            cli_profile,
+            profile: ProfileFileProvider::with_env(env),
+            ecs_ram_role,
+            credentials_uri,
+            selected: Mutex::new(None),
+            failure: Mutex::new(None),
+        }
+    }
+
+    /// `region_id` of the `aliyun` CLI profile, from the same read of the config file as
+    /// the chain's credentials; `None` when the file does not exist or the source is
+    /// disabled.
+    pub(crate) fn profile_region(&self) -> Result<Option<RegionId>, Error> {
+        match &self.cli_profile {
+            Some(cli_profile) if cli_profile.is_configured() => cli_profile.region_id(),
+            _ => Ok(None),
+        }
+    }
+
+    /// Name of the source the chain resolved credentials from, if any.
+    pub fn selected_source(&self) -> Option<&'static str> {
+        self.selected().map(Source::name)
+    }
+
+    fn selected(&self) -> Option<Source> {
+        *self.selected.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn select(&self, source: Source, credentials: Credentials) -> Credentials {
+        *self.selected.lock().unwrap_or_else(PoisonError::into_inner) = Some(source);
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            source = source.name(),
+            "resolved credentials from default chain"
+        );
+        credentials.with_provider_name(source.name())
+    }
+
+    /// The error of the last lookup that found no source, while it is recent enough to
+    /// reuse.
+    fn recent_failure(&self) -> Option<Error> {
+        let failure = self.failure.lock().unwrap_or_else(PoisonError::into_inner);
+        failure
+            .as_ref()
+            .filter(|(at, _)| at.elapsed() < FAILURE_BACKOFF)
+            .map(|(_, message)| Error::auth(message.clone()))
+    }
+
+    fn no_credentials(&self, tried: &[String]) -> Error {
+        let message = format!(
+            "no credentials found in the default chain (tried {})",
+            tried.join("; ")
+        );
+        *self.failure.lock().unwrap_or_else(PoisonError::into_inner) =
+            Some((Instant::now(), message.clone()));
+        Error::auth(message)
+    }
+
+    /// Drop the credentials cached by the refreshing sources; the selected source is kept.
+    fn invalidate_cache(&self) {
+        if let Some(oidc) = &self.oidc {
+            oidc.invalidate_cache();
+        }
+        if let Some(cli_profile) = &self.cli_profile {
+            cli_profile.invalidate_cache();
+        }
+        if let Some(ecs_ram_role) = &self.ecs_ram_role {
+            ecs_ram_role.invalidate_cache();
+        }
+        if let Some(credentials_uri) = &self.credentials_uri {
+            credentials_uri.invalidate_cache();
+        }
+    }
+
+    fn not_configured(source: Source) -> Error {
+        match source {
+            Source::Oidc => Error::auth(format!(
+                "{ROLE_ARN}, {OIDC_PROVIDER_ARN} or {OIDC_TOKEN_FILE} is not set"
+            )),
+            Source::CliProfile => Error::auth(format!("disabled by {CLI_PROFILE_DISABLED}")),
+            Source::EcsRamRole => Error::auth(format!("disabled by {ECS_METADATA_DISABLED}")),
+            Source::CredentialsUri => Error::auth(format!("{CREDENTIALS_URI} is not set")),
+            Source::Environment | Source::Profile => Error::auth("not configured"),
+        }
+    }
+
+    #[cfg(feature = "async")]
+    async fn fetch_from(&self, source: Source) -> Result<Credentials, Error> {
+        match source {
+            Source::Environment => self.environment.load(),
+            Source::Oidc => match &self.oidc {
+                Some(oidc) => oidc.fetch().await,
+                None => Err(Self::not_configured(source)),
+            },
+            Source::CliProfile => match &self.cli_profile {
+                Some(cli_profile) => cli_profile.fetch().await,
+                None => Err(Self::not_configured(source)),
+            },
+            Source::Profile => self.profile.load(),
+            Source::EcsRamRole => match &self.ecs_ram_role {
+                Some(ecs_ram_role) => ecs_ram_role.fetch().await,
+                None => Err(Self::not_configured(source)),
+            },
+            Source::CredentialsUri => match &self.credentials_uri {
+                Some(credentials_uri) => credentials_uri.fetch().await,
+                None => Err(Self::not_configured(source)),
+            },
+        }
+    }
+
+    #[cfg(feature = "blocking")]
+    fn fetch_from_blocking(&self, source: Source) -> Result<Credentials, Error> {
+        match source {
+            Source::Environment => self.environment.load(),
+            Source::Oidc => match &self.oidc {
+                Some(oidc) => oidc.fetch_blocking(),
+                None => Err(Self::not_configured(source)),
+            },
+            Source::CliProfile => match &self.cli_profile {
+                Some(cli_profile) => cli_profile.fetch_blocking(),
+                None => Err(Self::not_configured(source)),
+            },
+            Source::Profile => self.profile.load(),
+            Source::EcsRamRole => match &self.ecs_ram_role {
+                Some(ecs_ram_role) => ecs_ram_role.fetch_blocking(),
+                None => Err(Self::not_configured(source)),
+            },
+            Source::CredentialsUri => match &self.credentials_uri {
+                Some(credentials_uri) => credentials_uri.fetch_blocking(),
+                None => Err(Self::not_configured(source)),
+            },
+        }
+    }
+
+    #[cfg(feature = "async")]
+    async fn fetch(&self) -> Result<Credentials, Error> {
+        if let Some(source) = self.selected() {
+            return Ok(self
+                .fetch_from(source)
+                .await?
+                .with_provider_name(source.name()));
+        }
+        if let Some(error) = self.recent_failure() {
+            return Err(error);
+        }
+
+        let mut tried = Vec::with_capacity(Source::ORDER.len());
+        for source in Source::ORDER {
+            match self.fetch_from(source).await {
+                Ok(credentials) => return Ok(self.select(source, credentials)),
+                Err(error) => tried.push(describe_failure(source, &error)),
+            }
+        }
+        Err(self.no_credentials(&tried))
+    }
+
+    #[cfg(feature = "blocking")]
+    fn fetch_blocking(&self) -> Result<Credentials, Error> {
+        if let Some(source) = self.selected() {
+            return Ok(self
+                .fetch_from_blocking(source)?
+                .with_provider_name(source.name()));
+        }
+        if let Some(error) = self.recent_failure() {
+            return Err(error);
+        }
+
+        let mut tried = Vec::with_capacity(Source::ORDER.len());
+        for source in Source::ORDER {
+            match self.fetch_from_blocking(source) {
+                Ok(credentials) => return Ok(self.select(source, credentials)),
+                Err(error) => tried.push(describe_failure(source, &error)),
+            }
+        }
+        Err(self.no_credentials(&tried))
+    }
+}
+
+fn describe_failure(source: Source, error: &Error) -> String {
+    let reason = error
+        .message()
+        .map(str::to_owned)
+        .unwrap_or_else(|| error.to_string());
+    format!("{}: {reason}", source.name())
+}
+
+#[cfg(feature = "async")]
+impl CredentialsProvider for DefaultCredentialsChain {
+    fn provide_credentials(&self) -> CredentialsFuture<'_> {
+        Box::pin(self.fetch())
+    }
+
+    fn invalidate(&self) {
+        self.invalidate_cache();
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl BlockingCredentialsProvider for DefaultCredentialsChain {
+    fn provide_credentials_blocking(&self) -> Result<Credentials, Error> {
+        self.fetch_blocking()
+    }
+
+    fn invalidate(&self) {
+        self.invalidate_cache();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "blocking")]
+    use crate::auth::env::{ACCESS_KEY_ID, ACCESS_KEY_SECRET};
+
+    fn isolated_env(extra: &[(&str, &str)]) -> Env {
+        let home = std::env::temp_dir().join(format!("alibabacloud-home-{}", uuid::Uuid::new_v4()));
+        let home = home.to_str().unwrap().to_owned();
+        let mut pairs = vec![("HOME", home.as_str()), (ECS_METADATA_DISABLED, "true")];
+        pairs.extend_from_slice(extra);
+        Env::from_pairs(&pairs)
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn environment_is_selected_first() {
+        let chain = DefaultCredentialsChain::with_env(isolated_env(&[
+            (ACCESS_KEY_ID, "id"),
+            (ACCESS_KEY_SECRET, "secret"),
+        ]));
+
+        let credentials = chain.fetch_blocking().unwrap();
+        assert_eq!(credentials.access_key_id(), "id");
+        assert_eq!(credentials.provider_name(), Some("environment"));
+        assert_eq!(chain.selected_source(), Some("environment"));
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn failure_lists_every_source_tried() {
+        let chain = DefaultCredentialsChain::with_env(isolated_env(&[]));
+
+        let err = chain.fetch().await.unwrap_err();
+        assert!(err.is_auth_error());
+        let message = err.message().unwrap();
+        for source in Source::ORDER {
+            assert!(message.contains(source.name()), "{message}");
+        }
+        assert!(message.contains(ECS_METADATA_DISABLED));
+        assert_eq!(chain.selected_source(), None);
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn failed_lookup_is_reused_until_the_backoff_ends() {
+        let env = isolated_env(&[]);
+        let home = env.home_dir().unwrap();
+        let chain = DefaultCredentialsChain::with_env(env);
+        let err = chain.fetch_blocking().unwrap_err();
+
+        // Credentials appearing during the back-off are not looked for yet.
+        std::fs::create_dir_all(home.join(".aliyun")).unwrap();
+        std::fs::write(
+            home.join(".aliyun").join("config.json"),
+            r#"{"current":"dev","profiles":[{"name":"dev","mode":"AK","access_key_id":"cli-id","access_key_secret":"cli-secret"}]}"#,
+        )
+        .unwrap();
+        let again = chain.fetch_blocking().unwrap_err();
+        assert_eq!(again.message(), err.message());
+
+        *chain.failure.lock().unwrap() = Some((
+            Instant::now() - FAILURE_BACKOFF,
+            err.message().unwrap().to_owned(),
+        ));
+        let credentials = chain.fetch_blocking().unwrap();
+        std::fs::remove_dir_all(&home).unwrap();
+        assert_eq!(credentials.access_key_id(), "cli-id");
+        assert_eq!(chain.selected_source(), Some("cli_profile"));
+    }
+}
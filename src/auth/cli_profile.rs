@@ -0,0 +1,589 @@
+use std::{
+    fmt,
+    path::PathBuf,
+    sync::{Arc, OnceLock},
+};
+
+use serde::Deserialize;
+
+#[cfg(feature = "blocking")]
+use super::provider::BlockingCredentialsProvider;
+#[cfg(feature = "async")]
+use super::provider::{CredentialsFuture, CredentialsProvider};
+use super::{
+    AccessKey, Auth,
+    assume_role::RamRoleArnProvider,
+    ecs_ram_role::EcsRamRoleProvider,
+    env::Env,
+    oidc::{OidcRoleArnProvider, default_session_name},
+    process::ProcessCredentialsProvider,
+    provider::{Credentials, SharedCredentialsProvider},
+    uri::CredentialsUriProvider,
+};
+use crate::{
+    error::Error,
+    types::{RegionId, sts::AssumeRoleParams},
+};
+
+pub(crate) const PROFILE: &str = "ALIBABA_CLOUD_PROFILE";
+const DEFAULT_PROFILE: &str = "default";
+const PROVIDER_NAME: &str = "cli_profile";
+
+/// Reads credentials from a profile of the `aliyun` CLI configuration (`~/.aliyun/config.json`).
+///
+/// The profile is the one selected with [`profile`](Self::profile), otherwise
+/// `ALIBABA_CLOUD_PROFILE`, otherwise the file's `current` profile, otherwise `default`.
+///
+/// Supported modes are `AK`, `StsToken`, `RamRoleArn`, `EcsRamRole`, `ChainableRamRoleArn`,
+/// `OIDC`, `CredentialsURI` and `External` (`process_command`). Role-based modes call STS (or
+/// the ECS metadata service) on every refresh; the profile itself is parsed once.
+#[derive(Debug)]
+pub struct CliProfileProvider {
+    path: Option<PathBuf>,
+    profile: Option<String>,
+    env: Env,
+    file: OnceLock<ConfigFile>,
+    resolved: OnceLock<Arc<Resolved>>,
+}
+
+impl Default for CliProfileProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CliProfileProvider {
+    pub fn new() -> Self {
+        Self::with_env(Env::real())
+    }
+
+    pub(crate) fn with_env(env: Env) -> Self {
+        Self {
+            path: None,
+            profile: None,
+            env,
+            file: OnceLock::new(),
+            resolved: OnceLock::new(),
+        }
+    }
+
+    /// Read this file instead of `~/.aliyun/config.json`.
+    pub fn path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    /// Select a profile by name, overriding `ALIBABA_CLOUD_PROFILE` and the file's `current`.
+    pub fn profile(mut self, profile: impl Into<String>) -> Self {
+        self.profile = Some(profile.into());
+        self
+    }
+
+    /// The `region_id` of the selected profile, if it sets one.
+    pub fn region_id(&self) -> Result<Option<RegionId>, Error> {
+        let file = self.file()?;
+        let name = self.profile_name(&file.config);
+        let profile = file.config.find(&name)?;
+        Ok(non_empty(&profile.region_id).map(RegionId::from))
+    }
+
+    /// Whether the config file exists, i.e. whether [`region_id`](Self::region_id) and the
+    /// credentials can be read at all.
+    pub(crate) fn is_configured(&self) -> bool {
+        self.config_path().is_ok_and(|path| path.is_file())
+    }
+
+    fn resolved(&self) -> Result<Arc<Resolved>, Error> {
+        if let Some(resolved) = self.resolved.get() {
+            return Ok(resolved.clone());
+        }
+        let file = self.file()?;
+        let name = self.profile_name(&file.config);
+        let resolved = Resolver {
+            config: &file.config,
+            path: &file.path,
+            env: &self.env,
+        }
+        .resolve(&name, &mut Vec::new())?;
+        Ok(self.resolved.get_or_init(|| Arc::new(resolved)).clone())
+    }
+
+    pub(crate) fn invalidate_cache(&self) {
+        if let Some(resolved) = self.resolved.get() {
+            resolved.invalidate_cache();
+        }
+    }
+
+    #[cfg(feature = "async")]
+    pub(crate) async fn fetch(&self) -> Result<Credentials, Error> {
+        Ok(self
+            .resolved()?
+            .fetch()
+            .await?
+            .with_provider_name(PROVIDER_NAME))
+    }
+
+    #[cfg(feature = "blocking")]
+    pub(crate) fn fetch_blocking(&self) -> Result<Credentials, Error> {
+        Ok(self
+            .resolved()?
+            .fetch_blocking()?
+            .with_provider_name(PROVIDER_NAME))
+    }
+
+    fn profile_name(&self, config: &CliConfig) -> String {
+        self.profile
+            .clone()
+            .or_else(|| self.env.get(PROFILE))
+            .or_else(|| non_empty(&config.current))
+            .unwrap_or_else(|| DEFAULT_PROFILE.to_owned())
+    }
+
+    fn config_path(&self) -> Result<PathBuf, Error> {
+        match &self.path {
+            Some(path) => Ok(path.clone()),
+            None => self
+                .env
+                .home_dir()
+                .map(|home| home.join(".aliyun").join("config.json"))
+                .ok_or_else(|| {
+                    Error::auth("cannot locate aliyun CLI config: home directory is unknown")
+                }),
+        }
+    }
+
+    /// The parsed config file, read on first use and shared by the region lookup and the
+    /// credentials resolution.
+    fn file(&self) -> Result<&ConfigFile, Error> {
+        if let Some(file) = self.file.get() {
+            return Ok(file);
+        }
+        let file = self.read_config()?;
+        Ok(self.file.get_or_init(|| file))
+    }
+
+    fn read_config(&self) -> Result<ConfigFile, Error> {
+        let path = self.config_path()?;
+        let text = std::fs::read(&path).map_err(|e| {
+            Error::auth(format!(
+                "failed to read aliyun CLI config {}: {e}",
+                path.display()
+            ))
+        })?;
+        let config = serde_json::from_slice(&text).map_err(|e| {
+            Error::auth(format!("invalid aliyun CLI config {}: {e}", path.display()))
+        })?;
+        Ok(ConfigFile { config, path })
+    }
+}
+
+#[cfg(feature = "async")]
+impl CredentialsProvider for CliProfileProvider {
+    fn provide_credentials(&self) -> CredentialsFuture<'_> {
+        Box::pin(self.fetch())
+    }
+
+    fn invalidate(&self) {
+        self.invalidate_cache();
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl BlockingCredentialsProvider for CliProfileProvider {
+    fn provide_credentials_blocking(&self) -> Result<Credentials, Error> {
+        self.fetch_blocking()
+    }
+
+    fn invalidate(&self) {
+        self.invalidate_cache();
+    }
+}
+
+struct ConfigFile {
+    config: CliConfig,
+    path: PathBuf,
+}
+
+impl fmt::Debug for ConfigFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConfigFile")
+            .field("path", &self.path)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct CliConfig {
+    current: String,
+    profiles: Vec<CliProfile>,
+}
+
+impl CliConfig {
+    fn find(&self, name: &str) -> Result<&CliProfile, Error> {
+        self.profiles
+            .iter()
+            .find(|profile| profile.name == name)
+            .ok_or_else(|| Error::auth(format!("aliyun CLI profile {name} not found")))
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct CliProfile {
+    name: String,
+    mode: String,
+    access_key_id: String,
+    access_key_secret: String,
+    sts_token: String,
+    sts_region: String,
+    ram_role_name: String,
+    ram_role_arn: String,
+    ram_session_name: String,
+    source_profile: String,
+    external_id: String,
+    expired_seconds: u32,
+    oidc_provider_arn: String,
+    oidc_token_file: String,
+    credentials_uri: String,
+    process_command: String,
+    region_id: String,
+}
+
+/// The credentials source a profile resolved to.
+#[derive(Debug)]
+enum Resolved {
+    Static(Credentials),
+    RamRoleArn(Box<RamRoleArnProvider>),
+    EcsRamRole(EcsRamRoleProvider),
+    Oidc(Box<OidcRoleArnProvider>),
+    CredentialsUri(CredentialsUriProvider),
+    Process(ProcessCredentialsProvider),
+}
+
+impl Resolved {
+    fn invalidate_cache(&self) {
+        match self {
+            Resolved::Static(_) | Resolved::RamRoleArn(_) => {}
+            Resolved::EcsRamRole(provider) => provider.invalidate_cache(),
+            Resolved::Oidc(provider) => provider.invalidate_cache(),
+            Resolved::CredentialsUri(provider) => provider.invalidate_cache(),
+            Resolved::Process(provider) => provider.invalidate_cache(),
+        }
+    }
+
+    #[cfg(feature = "async")]
+    async fn fetch(&self) -> Result<Credentials, Error> {
+        match self {
+            Resolved::Static(credentials) => Ok(credentials.clone()),
+            Resolved::RamRoleArn(provider) => provider.fetch().await,
+            Resolved::EcsRamRole(provider) => provider.fetch().await,
+            Resolved::Oidc(provider) => provider.fetch().await,
+            Resolved::CredentialsUri(provider) => provider.fetch().await,
+            Resolved::Process(provider) => provider.fetch().await,
+        }
+    }
+
+    #[cfg(feature = "blocking")]
+    fn fetch_blocking(&self) -> Result<Credentials, Error> {
+        match self {
+            Resolved::Static(credentials) => Ok(credentials.clone()),
+            Resolved::RamRoleArn(provider) => provider.fetch_blocking(),
+            Resolved::EcsRamRole(provider) => provider.fetch_blocking(),
+            Resolved::Oidc(provider) => provider.fetch_blocking(),
+            Resolved::CredentialsUri(provider) => provider.fetch_blocking(),
+            Resolved::Process(provider) => provider.fetch_blocking(),
+        }
+    }
+}
+
+/// Source credentials of a `ChainableRamRoleArn` profile.
+#[derive(Debug)]
+struct SourceProfile(Resolved);
+
+#[cfg(feature = "async")]
+impl CredentialsProvider for SourceProfile {
+    fn provide_credentials(&self) -> CredentialsFuture<'_> {
+        Box::pin(self.0.fetch())
+    }
+
+    fn invalidate(&self) {
+        self.0.invalidate_cache();
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl BlockingCredentialsProvider for SourceProfile {
+    fn provide_credentials_blocking(&self) -> Result<Credentials, Error> {
+        self.0.fetch_blocking()
+    }
+
+    fn invalidate(&self) {
+        self.0.invalidate_cache();
+    }
+}
+
+struct Resolver<'a> {
+    config: &'a CliConfig,
+    path: &'a std::path::Path,
+    env: &'a Env,
+}
+
+impl Resolver<'_> {
+    fn resolve(&self, name: &str, visited: &mut Vec<String>) -> Result<Resolved, Error> {
+        if visited.iter().any(|seen| seen == name) {
+            visited.push(name.to_owned());
+            return Err(Error::auth(format!(
+                "aliyun CLI profiles form a source_profile cycle: {}",
+                visited.join(" -> ")
+            )));
+        }
+        visited.push(name.to_owned());
+
+        let profile = self.config.find(name).map_err(|_| {
+            Error::auth(format!(
+                "aliyun CLI profile {name} not found in {}",
+                self.path.display()
+            ))
+        })?;
+        let required = |field: &str, value: &str| {
+            non_empty(value)
+                .ok_or_else(|| Error::auth(format!("aliyun CLI profile {name} is missing {field}")))
+        };
+
+        match profile.mode.as_str() {
+            "AK" | "" => Ok(Resolved::Static(Credentials::new(
+                required("access_key_id", &profile.access_key_id)?,
+                required("access_key_secret", &profile.access_key_secret)?,
+            ))),
+            "StsToken" => Ok(Resolved::Static(
+                Credentials::new(
+                    required("access_key_id", &profile.access_key_id)?,
+                    required("access_key_secret", &profile.access_key_secret)?,
+                )
+                .with_security_token(required("sts_token", &profile.sts_token)?),
+            )),
+            "RamRoleArn" => {
+                let mut access_key = AccessKey::new(
+                    required("access_key_id", &profile.access_key_id)?,
+                    required("access_key_secret", &profile.access_key_secret)?,
+                );
+                if let Some(token) = non_empty(&profile.sts_token) {
+                    access_key = access_key.with_security_token(token);
+                }
+                self.ram_role_arn(profile, Auth::AccessKey(access_key))
+            }
+            "ChainableRamRoleArn" => {
+                let source_name = required("source_profile", &profile.source_profile)?;
+                let source = self.resolve(&source_name, visited)?;
+                let source = Auth::Provider(SharedCredentialsProvider::from_builtin(
+                    SourceProfile(source),
+                ));
+                self.ram_role_arn(profile, source)
+            }
+            "EcsRamRole" => {
+                let mut provider = EcsRamRoleProvider::with_env(self.env);
+                if let Some(role_name) = non_empty(&profile.ram_role_name) {
+                    provider = provider.role_name(role_name);
+                }
+                Ok(Resolved::EcsRamRole(provider))
+            }
+            "OIDC" => {
+                let mut provider = OidcRoleArnProvider::with_sts_endpoint(
+                    required("ram_role_arn", &profile.ram_role_arn)?,
+                    required("oidc_provider_arn", &profile.oidc_provider_arn)?,
+                    PathBuf::from(required("oidc_token_file", &profile.oidc_token_file)?),
+                    self.sts_endpoint(profile),
+                );
+                if let Some(role_session_name) = non_empty(&profile.ram_session_name) {
+                    provider = provider.role_session_name(role_session_name);
+                }
+                if profile.expired_seconds > 0 {
+                    provider = provider.duration_seconds(profile.expired_seconds);
+                }
+                Ok(Resolved::Oidc(Box::new(provider)))
+            }
+            "CredentialsURI" => Ok(Resolved::CredentialsUri(CredentialsUriProvider::new(
+                required("credentials_uri", &profile.credentials_uri)?,
+            ))),
+            "External" => Ok(Resolved::Process(
+                ProcessCredentialsProvider::from_command_line(&required(
+                    "process_command",
+                    &profile.process_command,
+                )?)?,
+            )),
+            other => Err(Error::auth(format!(
+                "aliyun CLI profile {name} uses unsupported mode {other}"
+            ))),
+        }
+    }
+
+    fn ram_role_arn(&self, profile: &CliProfile, source: Auth) -> Result<Resolved, Error> {
+        let role_arn = non_empty(&profile.ram_role_arn).ok_or_else(|| {
+            Error::auth(format!(
+                "aliyun CLI profile {} is missing ram_role_arn",
+                profile.name
+            ))
+        })?;
+        let params = AssumeRoleParams {
+            role_arn,
+            role_session_name: non_empty(&profile.ram_session_name)
+                .unwrap_or_else(default_session_name),
+            policy: None,
+            duration_seconds: (profile.expired_seconds > 0).then_some(profile.expired_seconds),
+            external_id: non_empty(&profile.external_id),
+        };
+        Ok(Resolved::RamRoleArn(Box::new(RamRoleArnProvider::new(
+            source,
+            params,
+            self.sts_endpoint(profile),
+        ))))
+    }
+
+    fn sts_endpoint(&self, profile: &CliProfile) -> String {
+        match non_empty(&profile.sts_region) {
+            Some(region) => format!("https://sts.{region}.aliyuncs.com/"),
+            None => self.env.sts_endpoint(),
+        }
+    }
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"{
+        "current": "dev",
+        "profiles": [
+            {
+                "name": "default",
+                "mode": "AK",
+                "access_key_id": "default-id",
+                "access_key_secret": "default-secret",
+                "region_id": "cn-hangzhou"
+            },
+            {
+                "name": "dev",
+                "mode": "StsToken",
+                "access_key_id": "dev-id",
+                "access_key_secret": "dev-secret",
+                "sts_token": "dev-token",
+                "region_id": "cn-shanghai"
+            },
+            {
+                "name": "role",
+                "mode": "ChainableRamRoleArn",
+                "source_profile": "default",
+                "ram_role_arn": "acs:ram::123:role/test",
+                "expired_seconds": 900
+            },
+            {"name": "loop-a", "mode": "ChainableRamRoleArn", "source_profile": "loop-b", "ram_role_arn": "a"},
+            {"name": "loop-b", "mode": "ChainableRamRoleArn", "source_profile": "loop-a", "ram_role_arn": "b"},
+            {"name": "vault", "mode": "External", "process_command": "vault-creds --role ops"},
+            {"name": "sso", "mode": "CloudSSO"}
+        ]
+    }"#;
+
+    fn with_config(test: impl FnOnce(PathBuf)) {
+        let path =
+            std::env::temp_dir().join(format!("aliyun-cli-config-{}.json", uuid::Uuid::new_v4()));
+        std::fs::write(&path, CONFIG).unwrap();
+        test(path.clone());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    fn resolve(path: &std::path::Path, env: &Env, name: &str) -> Result<Resolved, Error> {
+        let config = serde_json::from_str(CONFIG).unwrap();
+        Resolver {
+            config: &config,
+            path,
+            env,
+        }
+        .resolve(name, &mut Vec::new())
+    }
+
+    #[test]
+    fn selects_explicit_then_env_then_current_profile() {
+        with_config(|path| {
+            let current = CliProfileProvider::with_env(Env::from_pairs(&[])).path(&path);
+            assert_eq!(current.region_id().unwrap(), Some("cn-shanghai".into()));
+
+            let from_env =
+                CliProfileProvider::with_env(Env::from_pairs(&[(PROFILE, "default")])).path(&path);
+            assert_eq!(from_env.region_id().unwrap(), Some("cn-hangzhou".into()));
+
+            let explicit = CliProfileProvider::with_env(Env::from_pairs(&[(PROFILE, "default")]))
+                .path(&path)
+                .profile("role");
+            assert_eq!(explicit.region_id().unwrap(), None);
+        });
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn region_and_credentials_share_one_read_of_the_file() {
+        let mut provider = None;
+        with_config(|path| {
+            let cli_profile = CliProfileProvider::with_env(Env::from_pairs(&[])).path(&path);
+            assert_eq!(cli_profile.region_id().unwrap(), Some("cn-shanghai".into()));
+            provider = Some(cli_profile);
+        });
+
+        let credentials = provider.unwrap().fetch_blocking().unwrap();
+        assert_eq!(credentials.access_key_id(), "dev-id");
+    }
+
+    #[test]
+    fn maps_static_and_role_modes() {
+        let env = Env::from_pairs(&[]);
+        let path = std::path::Path::new("config.json");
+
+        let Resolved::Static(credentials) = resolve(path, &env, "dev").unwrap() else {
+            panic!("StsToken should resolve to static credentials");
+        };
+        assert_eq!(credentials.access_key_id(), "dev-id");
+        assert!(credentials.access_key().has_security_token());
+
+        assert!(matches!(
+            resolve(path, &env, "role").unwrap(),
+            Resolved::RamRoleArn(_)
+        ));
+        assert!(matches!(
+            resolve(path, &env, "vault").unwrap(),
+            Resolved::Process(_)
+        ));
+    }
+
+    #[test]
+    fn rejects_source_profile_cycles_and_unsupported_modes() {
+        let env = Env::from_pairs(&[]);
+        let path = std::path::Path::new("config.json");
+
+        let err = resolve(path, &env, "loop-a").unwrap_err();
+        assert!(
+            err.message()
+                .unwrap()
+                .contains("loop-a -> loop-b -> loop-a"),
+            "{err}"
+        );
+
+        let err = resolve(path, &env, "sso").unwrap_err();
+        assert!(err.message().unwrap().contains("unsupported mode CloudSSO"));
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn provides_static_profile_credentials() {
+        with_config(|path| {
+            let provider = CliProfileProvider::with_env(Env::from_pairs(&[])).path(&path);
+            let credentials = provider.fetch_blocking().unwrap();
+            assert_eq!(credentials.access_key_id(), "dev-id");
+            assert_eq!(credentials.provider_name(), Some("cli_profile"));
+        });
+    }
+}
@@ -0,0 +1,308 @@
+use std::time::Duration;
+
+use http::{HeaderMap, HeaderValue, Method};
+
+#[cfg(feature = "blocking")]
+use super::provider::BlockingCredentialsProvider;
+#[cfg(feature = "async")]
+use super::provider::{CredentialsFuture, CredentialsProvider};
+use super::{
+    env::Env,
+    http::CredentialsHttp,
+    provider::{Credentials, CredentialsCache, SessionCredentials},
+};
+use crate::{
+    error::Error,
+    transport::{Body, Request, Response},
+};
+
+const ECS_METADATA: &str = "ALIBABA_CLOUD_ECS_METADATA";
+const IMDSV1_DISABLED: &str = "ALIBABA_CLOUD_IMDSV1_DISABLED";
+const METADATA_BASE_URL: &str = "http://100.100.100.200/";
+const TOKEN_PATH: &str = "latest/api/token";
+const CREDENTIALS_PATH: &str = "latest/meta-data/ram/security-credentials/";
+const TOKEN_HEADER: &str = "x-aliyun-ecs-metadata-token";
+const TOKEN_TTL_HEADER: &str = "x-aliyun-ecs-metadata-token-ttl-seconds";
+const TOKEN_TTL_SECONDS: &str = "21600";
+const PROVIDER_NAME: &str = "ecs_ram_role";
+
+/// Fetches the instance RAM role credentials from the ECS metadata service.
+///
+/// Requests are hardened with an IMDSv2 session token (`X-aliyun-ecs-metadata-token`).
+/// When the token cannot be obtained the provider falls back to plain (IMDSv1) requests,
+/// unless [`imds_v1_fallback(false)`](Self::imds_v1_fallback) or
+/// `ALIBABA_CLOUD_IMDSV1_DISABLED=true` is set.
+///
+/// The role name defaults to `ALIBABA_CLOUD_ECS_METADATA`, or is discovered from the
+/// metadata service. Credentials are cached and fetched again shortly before their
+/// `Expiration`.
+#[derive(Debug)]
+pub struct EcsRamRoleProvider {
+    role_name: Option<String>,
+    base_url: String,
+    imds_v1_fallback: bool,
+    http: CredentialsHttp,
+    cached: CredentialsCache,
+}
+
+impl Default for EcsRamRoleProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EcsRamRoleProvider {
+    pub fn new() -> Self {
+        Self::with_env(&Env::real())
+    }
+
+    pub(crate) fn with_env(env: &Env) -> Self {
+        Self {
+            role_name: env.get(ECS_METADATA),
+            base_url: METADATA_BASE_URL.to_owned(),
+            imds_v1_fallback: !env.is_true(IMDSV1_DISABLED),
+            http: CredentialsHttp::new(Duration::from_secs(1), Duration::from_secs(1)),
+            cached: CredentialsCache::new(),
+        }
+    }
+
+    /// Use this RAM role instead of discovering it from the metadata service.
+    pub fn role_name(mut self, role_name: impl Into<String>) -> Self {
+        self.role_name = Some(role_name.into());
+        self
+    }
+
+    /// Metadata service base URL (default `http://100.100.100.200/`).
+    pub fn metadata_base_url(mut self, base_url: impl AsRef<str>) -> Self {
+        self.base_url = base_url.as_ref().to_owned();
+        self
+    }
+
+    /// Whether to fall back to requests without a metadata token when IMDSv2 is unavailable.
+    pub fn imds_v1_fallback(mut self, enabled: bool) -> Self {
+        self.imds_v1_fallback = enabled;
+        self
+    }
+
+    pub(crate) fn invalidate_cache(&self) {
+        self.cached.clear();
+    }
+
+    #[cfg(feature = "async")]
+    pub(crate) async fn fetch(&self) -> Result<Credentials, Error> {
+        if let Some(credentials) = self.cached.fresh() {
+            return Ok(credentials);
+        }
+
+        let token = self.token(self.http.send(self.token_request()?).await)?;
+        let role_name = match &self.role_name {
+            Some(role_name) => role_name.clone(),
+            None => {
+                let request = self.request(None, token.as_ref())?;
+                parse_role_name(self.http.send(request).await?)?
+            }
+        };
+        let request = self.request(Some(&role_name), token.as_ref())?;
+        let credentials = parse_credentials(self.http.send(request).await?)?;
+        Ok(self.cached.insert(credentials))
+    }
+
+    #[cfg(feature = "blocking")]
+    pub(crate) fn fetch_blocking(&self) -> Result<Credentials, Error> {
+        if let Some(credentials) = self.cached.fresh() {
+            return Ok(credentials);
+        }
+
+        let token = self.token(self.http.send_blocking(self.token_request()?))?;
+        let role_name = match &self.role_name {
+            Some(role_name) => role_name.clone(),
+            None => {
+                let request = self.request(None, token.as_ref())?;
+                parse_role_name(self.http.send_blocking(request)?)?
+            }
+        };
+        let request = self.request(Some(&role_name), token.as_ref())?;
+        let credentials = parse_credentials(self.http.send_blocking(request)?)?;
+        Ok(self.cached.insert(credentials))
+    }
+
+    fn token(&self, response: Result<Response, Error>) -> Result<Option<HeaderValue>, Error> {
+        let error = match response {
+            Ok(response) if response.status.is_success() => {
+                let token = String::from_utf8_lossy(&response.body);
+                return HeaderValue::from_str(token.trim())
+                    .map(Some)
+                    .map_err(|_| Error::auth("metadata service returned an invalid token"));
+            }
+            Ok(response) => Error::auth(format!(
+                "failed to get ECS metadata token (http status {})",
+                response.status
+            )),
+            Err(error) => error,
+        };
+
+        if self.imds_v1_fallback {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(error = %error, "falling back to ECS metadata requests without a token");
+            Ok(None)
+        } else {
+            Err(error)
+        }
+    }
+
+    fn token_request(&self) -> Result<Request, Error> {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            TOKEN_TTL_HEADER,
+            HeaderValue::from_static(TOKEN_TTL_SECONDS),
+        );
+        Ok(Request {
+            method: Method::PUT,
+            url: self.url(TOKEN_PATH, None)?,
+            headers,
+            body: Body::empty(),
+            timeout: self.http.timeout(),
+        })
+    }
+
+    fn request(
+        &self,
+        role_name: Option<&str>,
+        token: Option<&HeaderValue>,
+    ) -> Result<Request, Error> {
+        let mut headers = HeaderMap::new();
+        if let Some(token) = token {
+            headers.insert(TOKEN_HEADER, token.clone());
+        }
+        Ok(Request {
+            method: Method::GET,
+            url: self.url(CREDENTIALS_PATH, role_name)?,
+            headers,
+            body: Body::empty(),
+            timeout: self.http.timeout(),
+        })
+    }
+
+    fn url(&self, path: &str, role_name: Option<&str>) -> Result<url::Url, Error> {
+        let base_url = crate::util::url::parse_base_url(&self.base_url)?;
+        let mut url = base_url
+            .join(path)
+            .map_err(|e| Error::invalid_config("invalid metadata url", Some(Box::new(e))))?;
+        if let Some(role_name) = role_name {
+            url = crate::util::url::endpoint(&url, &[role_name])?;
+        }
+        Ok(url)
+    }
+}
+
+#[cfg(feature = "async")]
+impl CredentialsProvider for EcsRamRoleProvider {
+    fn provide_credentials(&self) -> CredentialsFuture<'_> {
+        Box::pin(self.fetch())
+    }
+
+    fn invalidate(&self) {
+        self.invalidate_cache();
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl BlockingCredentialsProvider for EcsRamRoleProvider {
+    fn provide_credentials_blocking(&self) -> Result<Credentials, Error> {
+        self.fetch_blocking()
+    }
+
+    fn invalidate(&self) {
+        self.invalidate_cache();
+    }
+}
+
+fn parse_role_name(response: Response) -> Result<String, Error> {
+    if !response.status.is_success() {
+        return Err(Error::auth(format!(
+            "failed to get ECS RAM role name from metadata service (http status {})",
+            response.status
+        )));
+    }
+    String::from_utf8_lossy(&response.body)
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(str::to_owned)
+        .ok_or_else(|| Error::auth("no RAM role is attached to this ECS instance"))
+}
+
+fn parse_credentials(response: Response) -> Result<Credentials, Error> {
+    if !response.status.is_success() {
+        return Err(Error::auth(format!(
+            "failed to get ECS RAM role credentials from metadata service (http status {})",
+            response.status
+        )));
+    }
+
+    #[derive(serde::Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct MetadataCredentials {
+        code: String,
+        #[serde(flatten)]
+        credentials: SessionCredentials,
+    }
+
+    let parsed: MetadataCredentials = serde_json::from_slice(&response.body)
+        .map_err(|e| Error::auth(format!("invalid ECS RAM role credentials response: {e}")))?;
+    if parsed.code != "Success" {
+        return Err(Error::auth(format!(
+            "metadata service returned ECS RAM role credentials with code {}",
+            parsed.code
+        )));
+    }
+    parsed.credentials.into_credentials(PROVIDER_NAME)
+}
+
+#[cfg(test)]
+mod tests {
+    use http::StatusCode;
+
+    use super::*;
+
+    fn response(status: StatusCode, body: &str) -> Response {
+        Response {
+            status,
+            headers: HeaderMap::new(),
+            body: body.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn token_failure_falls_back_to_imds_v1_unless_disabled() {
+        let provider = EcsRamRoleProvider::with_env(&Env::from_pairs(&[]));
+        let token = provider
+            .token(Ok(response(StatusCode::FORBIDDEN, "")))
+            .unwrap();
+        assert!(token.is_none());
+
+        let provider = EcsRamRoleProvider::with_env(&Env::from_pairs(&[(IMDSV1_DISABLED, "true")]));
+        let err = provider
+            .token(Ok(response(StatusCode::FORBIDDEN, "")))
+            .unwrap_err();
+        assert!(err.message().unwrap().contains("metadata token"));
+    }
+
+    #[test]
+    fn credentials_require_success_code() {
+        let err = parse_credentials(response(
+            StatusCode::OK,
+            r#"{"Code":"Failed","AccessKeyId":"id","AccessKeySecret":"s"}"#,
+        ))
+        .unwrap_err();
+        assert!(err.message().unwrap().contains("code Failed"));
+
+        let credentials = parse_credentials(response(
+            StatusCode::OK,
+            r#"{"Code":"Success","AccessKeyId":"id","AccessKeySecret":"s","SecurityToken":"t","Expiration":"2030-01-01T00:00:00Z"}"#,
+        ))
+        .unwrap();
+        assert!(credentials.access_key().has_security_token());
+        assert!(credentials.expiration().is_some());
+    }
+}
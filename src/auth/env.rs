@@ -0,0 +1,152 @@
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
+
+#[cfg(feature = "blocking")]
+use super::provider::BlockingCredentialsProvider;
+use super::provider::Credentials;
+#[cfg(feature = "async")]
+use super::provider::{CredentialsFuture, CredentialsProvider};
+use crate::error::Error;
+
+pub(crate) const ACCESS_KEY_ID: &str = "ALIBABA_CLOUD_ACCESS_KEY_ID";
+pub(crate) const ACCESS_KEY_SECRET: &str = "ALIBABA_CLOUD_ACCESS_KEY_SECRET";
+pub(crate) const SECURITY_TOKEN: &str = "ALIBABA_CLOUD_SECURITY_TOKEN";
+const STS_REGION: &str = "ALIBABA_CLOUD_STS_REGION";
+const VPC_ENDPOINT_ENABLED: &str = "ALIBABA_CLOUD_VPC_ENDPOINT_ENABLED";
+
+/// Process environment lookup that tests can replace with a fixed map.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Env {
+    overrides: Option<Arc<HashMap<String, String>>>,
+}
+
+impl Env {
+    pub(crate) fn real() -> Self {
+        Self { overrides: None }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn from_pairs(pairs: &[(&str, &str)]) -> Self {
+        let map = pairs
+            .iter()
+            .map(|(k, v)| ((*k).to_owned(), (*v).to_owned()))
+            .collect();
+        Self {
+            overrides: Some(Arc::new(map)),
+        }
+    }
+
+    /// Returns the trimmed value of `key`, treating empty values as unset.
+    pub(crate) fn get(&self, key: &str) -> Option<String> {
+        let value = match &self.overrides {
+            Some(map) => map.get(key).cloned(),
+            None => std::env::var(key).ok(),
+        }?;
+        let value = value.trim();
+        if value.is_empty() {
+            None
+        } else {
+            Some(value.to_owned())
+        }
+    }
+
+    pub(crate) fn is_true(&self, key: &str) -> bool {
+        self.get(key)
+            .is_some_and(|value| value.eq_ignore_ascii_case("true"))
+    }
+
+    pub(crate) fn home_dir(&self) -> Option<PathBuf> {
+        self.get("HOME")
+            .or_else(|| self.get("USERPROFILE"))
+            .map(PathBuf::from)
+    }
+
+    /// STS endpoint selected by `ALIBABA_CLOUD_STS_REGION` and
+    /// `ALIBABA_CLOUD_VPC_ENDPOINT_ENABLED`, as used by the official SDKs.
+    pub(crate) fn sts_endpoint(&self) -> String {
+        match self.get(STS_REGION) {
+            Some(region) if self.is_true(VPC_ENDPOINT_ENABLED) => {
+                format!("https://sts-vpc.{region}.aliyuncs.com/")
+            }
+            Some(region) => format!("https://sts.{region}.aliyuncs.com/"),
+            None => "https://sts.aliyuncs.com/".to_owned(),
+        }
+    }
+}
+
+/// Reads credentials from `ALIBABA_CLOUD_ACCESS_KEY_ID`, `ALIBABA_CLOUD_ACCESS_KEY_SECRET`
+/// and (optionally) `ALIBABA_CLOUD_SECURITY_TOKEN`.
+#[derive(Debug, Clone, Default)]
+pub struct EnvironmentProvider {
+    env: Env,
+}
+
+impl EnvironmentProvider {
+    pub fn new() -> Self {
+        Self { env: Env::real() }
+    }
+
+    pub(crate) fn with_env(env: Env) -> Self {
+        Self { env }
+    }
+
+    pub(crate) fn load(&self) -> Result<Credentials, Error> {
+        let access_key_id = self
+            .env
+            .get(ACCESS_KEY_ID)
+            .ok_or_else(|| Error::auth(format!("{ACCESS_KEY_ID} is not set")))?;
+        let access_key_secret = self
+            .env
+            .get(ACCESS_KEY_SECRET)
+            .ok_or_else(|| Error::auth(format!("{ACCESS_KEY_SECRET} is not set")))?;
+
+        let mut credentials =
+            Credentials::new(access_key_id, access_key_secret).with_provider_name("environment");
+        if let Some(token) = self.env.get(SECURITY_TOKEN) {
+            credentials = credentials.with_security_token(token);
+        }
+        Ok(credentials)
+    }
+}
+
+#[cfg(feature = "async")]
+impl CredentialsProvider for EnvironmentProvider {
+    fn provide_credentials(&self) -> CredentialsFuture<'_> {
+        let result = self.load();
+        Box::pin(async move { result })
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl BlockingCredentialsProvider for EnvironmentProvider {
+    fn provide_credentials_blocking(&self) -> Result<Credentials, Error> {
+        self.load()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_access_key_and_optional_token() {
+        let provider = EnvironmentProvider::with_env(Env::from_pairs(&[
+            (ACCESS_KEY_ID, "id"),
+            (ACCESS_KEY_SECRET, "secret"),
+            (SECURITY_TOKEN, "token"),
+        ]));
+
+        let credentials = provider.load().unwrap();
+        assert_eq!(credentials.access_key_id(), "id");
+        assert!(credentials.access_key().has_security_token());
+        assert_eq!(credentials.provider_name(), Some("environment"));
+    }
+
+    #[test]
+    fn missing_secret_is_reported() {
+        let provider = EnvironmentProvider::with_env(Env::from_pairs(&[(ACCESS_KEY_ID, "id")]));
+
+        let err = provider.load().unwrap_err();
+        assert!(err.is_auth_error());
+        assert!(err.message().unwrap().contains(ACCESS_KEY_SECRET));
+    }
+}
@@ -0,0 +1,94 @@
+use std::{sync::OnceLock, time::Duration};
+
+#[cfg(any(feature = "async", feature = "blocking"))]
+use std::sync::Arc;
+
+use crate::{
+    error::Error,
+    transport::{Request, Response},
+};
+
+#[cfg(feature = "async")]
+use crate::transport::{AsyncTransport, default_async_transport};
+#[cfg(feature = "blocking")]
+use crate::transport::{BlockingTransport, default_blocking_transport};
+#[cfg(any(feature = "async", feature = "blocking"))]
+use crate::transport::{ConnectionConfig, ProxyConfig};
+
+/// Lazily built transports used by credentials providers that talk HTTP.
+///
+/// Providers are shared between async and blocking clients, so each flavour's
+/// transport is only created on first use.
+pub(crate) struct CredentialsHttp {
+    connect_timeout: Duration,
+    timeout: Duration,
+    #[cfg(feature = "async")]
+    async_transport: OnceLock<Arc<dyn AsyncTransport>>,
+    #[cfg(feature = "blocking")]
+    blocking_transport: OnceLock<Arc<dyn BlockingTransport>>,
+}
+
+impl CredentialsHttp {
+    pub(crate) fn new(connect_timeout: Duration, timeout: Duration) -> Self {
+        Self {
+            connect_timeout,
+            timeout,
+            #[cfg(feature = "async")]
+            async_transport: OnceLock::new(),
+            #[cfg(feature = "blocking")]
+            blocking_transport: OnceLock::new(),
+        }
+    }
+
+    pub(crate) fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    fn connection(&self) -> ConnectionConfig {
+        ConnectionConfig {
+            connect_timeout: self.connect_timeout,
+            // The metadata service and credentials URIs are local endpoints.
+            proxy: ProxyConfig::Direct,
+            ..ConnectionConfig::default()
+        }
+    }
+
+    #[cfg(feature = "async")]
+    pub(crate) async fn send(&self, request: Request) -> Result<Response, Error> {
+        let transport = match self.async_transport.get() {
+            Some(transport) => transport.clone(),
+            None => {
+                let transport = default_async_transport(&self.connection())?;
+                self.async_transport.get_or_init(|| transport).clone()
+            }
+        };
+        transport
+            .send(request)
+            .await
+            .map_err(|e| Error::auth(format!("credentials request failed: {e}")))
+    }
+
+    #[cfg(feature = "blocking")]
+    pub(crate) fn send_blocking(&self, request: Request) -> Result<Response, Error> {
+        let transport = match self.blocking_transport.get() {
+            Some(transport) => transport.clone(),
+            None => {
+                let transport = default_blocking_transport(&self.connection())?;
+                self.blocking_transport.get_or_init(|| transport).clone()
+            }
+        };
+        transport
+            .send(request)
+            .map_err(|e| Error::auth(format!("credentials request failed: {e}")))
+    }
+}
+
+impl std::fmt::Debug for CredentialsHttp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CredentialsHttp")
+            .field("connect_timeout", &self.connect_timeout)
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
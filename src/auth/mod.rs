@@ -0,0 +1,208 @@
+use std::fmt;
+
+mod assume_role;
+mod chain;
+mod cli_profile;
+mod ecs_ram_role;
+mod env;
+mod http;
+mod oidc;
+mod process;
+mod profile;
+mod provider;
+mod secret;
+mod sts;
+mod uri;
+
+#[cfg(feature = "async")]
+pub use assume_role::AssumeRoleProvider;
+#[cfg(feature = "blocking")]
+pub use assume_role::BlockingAssumeRoleProvider;
+pub use chain::DefaultCredentialsChain;
+pub use cli_profile::CliProfileProvider;
+pub use ecs_ram_role::EcsRamRoleProvider;
+pub use env::EnvironmentProvider;
+pub use oidc::OidcRoleArnProvider;
+pub use process::ProcessCredentialsProvider;
+pub use profile::ProfileFileProvider;
+pub use secret::SecretString;
+pub use uri::CredentialsUriProvider;
+
+#[cfg(feature = "blocking")]
+pub use provider::BlockingCredentialsProvider;
+pub use provider::{Credentials, SharedCredentialsProvider};
+#[cfg(feature = "async")]
+pub use provider::{CredentialsFuture, CredentialsProvider};
+
+pub(crate) use env::Env;
+pub(crate) use provider::{CredentialsCache, parse_expiration};
+
+/// Authentication configuration.
+#[derive(Clone)]
+#[non_exhaustive]
+pub enum Auth {
+    /// No authentication.
+    None,
+    /// Alibaba Cloud access key authentication for RPC APIs.
+    AccessKey(AccessKey),
+    /// Credentials resolved through a (possibly refreshing) credentials provider.
+    Provider(SharedCredentialsProvider),
+}
+
+impl Auth {
+    /// Create a `None` authentication configuration.
+    pub fn none() -> Self {
+        Self::None
+    }
+
+    /// Create an access key authentication configuration.
+    pub fn access_key(
+        access_key_id: impl Into<String>,
+        access_key_secret: impl Into<String>,
+    ) -> Self {
+        Self::AccessKey(AccessKey::new(access_key_id, access_key_secret))
+    }
+
+    /// Create an access key authentication configuration with a session token.
+    pub fn access_key_with_security_token(
+        access_key_id: impl Into<String>,
+        access_key_secret: impl Into<String>,
+        security_token: impl Into<String>,
+    ) -> Self {
+        Self::AccessKey(
+            AccessKey::new(access_key_id, access_key_secret).with_security_token(security_token),
+        )
+    }
+
+    /// Resolve credentials through the [`DefaultCredentialsChain`]: environment variables,
+    /// OIDC role ARN, the `aliyun` CLI config, the shared credentials file, the ECS
+    /// instance RAM role, then the credentials URI.
+    pub fn default_chain() -> Self {
+        Self::Provider(SharedCredentialsProvider::from_builtin(
+            DefaultCredentialsChain::new(),
+        ))
+    }
+
+    /// Resolve credentials from a named profile of the `aliyun` CLI config
+    /// (`~/.aliyun/config.json`). See [`CliProfileProvider`].
+    pub fn from_profile(name: impl Into<String>) -> Self {
+        Self::Provider(SharedCredentialsProvider::from_builtin(
+            CliProfileProvider::new().profile(name),
+        ))
+    }
+
+    /// Resolve credentials by running an external command that prints credential JSON.
+    /// See [`ProcessCredentialsProvider`].
+    pub fn credential_process(provider: ProcessCredentialsProvider) -> Self {
+        Self::Provider(SharedCredentialsProvider::from_builtin(provider))
+    }
+
+    /// Resolve credentials through an async credentials provider (for `Client`).
+    #[cfg(feature = "async")]
+    pub fn provider(provider: impl CredentialsProvider + 'static) -> Self {
+        Self::Provider(SharedCredentialsProvider::new(provider))
+    }
+
+    /// Resolve credentials through a blocking credentials provider (for `BlockingClient`).
+    #[cfg(feature = "blocking")]
+    pub fn blocking_provider(provider: impl BlockingCredentialsProvider + 'static) -> Self {
+        Self::Provider(SharedCredentialsProvider::new_blocking(provider))
+    }
+}
+
+impl Default for Auth {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+impl fmt::Debug for Auth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Auth::None => f.debug_tuple("Auth::None").finish(),
+            Auth::AccessKey(access_key) => {
+                f.debug_tuple("Auth::AccessKey").field(access_key).finish()
+            }
+            Auth::Provider(provider) => f.debug_tuple("Auth::Provider").field(provider).finish(),
+        }
+    }
+}
+
+/// How RPC requests are signed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SignatureAlgorithm {
+    /// Signature version 1.0 (`HMAC-SHA1`), carried in the query string.
+    #[default]
+    HmacSha1,
+    /// Signature V3 (`ACS3-HMAC-SHA256`), carried in the `Authorization` header.
+    Acs3HmacSha256,
+    /// Signature V3 with the SM3 hash (`ACS3-HMAC-SM3`), for deployments that require
+    /// Chinese national cryptographic algorithms.
+    Acs3HmacSm3,
+}
+
+/// Access key credentials.
+#[derive(Clone)]
+pub struct AccessKey {
+    pub(crate) access_key_id: String,
+    pub(crate) access_key_secret: SecretString,
+    pub(crate) security_token: Option<SecretString>,
+}
+
+impl AccessKey {
+    /// Create access key credentials.
+    pub fn new(access_key_id: impl Into<String>, access_key_secret: impl Into<String>) -> Self {
+        Self {
+            access_key_id: access_key_id.into(),
+            access_key_secret: SecretString::new(access_key_secret),
+            security_token: None,
+        }
+    }
+
+    /// Create access key credentials from a secret loaded with, for example,
+    /// [`SecretString::from_file`].
+    pub fn from_secret(access_key_id: impl Into<String>, access_key_secret: SecretString) -> Self {
+        Self {
+            access_key_id: access_key_id.into(),
+            access_key_secret,
+            security_token: None,
+        }
+    }
+
+    /// Attach an STS security token.
+    pub fn with_security_token(mut self, security_token: impl Into<String>) -> Self {
+        self.security_token = Some(SecretString::new(security_token));
+        self
+    }
+
+    /// The access key ID.
+    pub fn access_key_id(&self) -> &str {
+        &self.access_key_id
+    }
+
+    /// The access key secret, for custom signers.
+    pub fn access_key_secret(&self) -> &SecretString {
+        &self.access_key_secret
+    }
+
+    /// The STS security token, for custom signers.
+    pub fn security_token(&self) -> Option<&SecretString> {
+        self.security_token.as_ref()
+    }
+
+    /// Whether an STS security token is attached.
+    pub fn has_security_token(&self) -> bool {
+        self.security_token.is_some()
+    }
+}
+
+impl fmt::Debug for AccessKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("AccessKey");
+        debug.field("access_key_id", &self.access_key_id);
+        debug.field("access_key_secret", &self.access_key_secret);
+        debug.field("security_token", &self.security_token);
+        debug.finish()
+    }
+}
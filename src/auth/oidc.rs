@@ -0,0 +1,205 @@
+use std::path::PathBuf;
+
+#[cfg(feature = "blocking")]
+use super::provider::BlockingCredentialsProvider;
+#[cfg(feature = "async")]
+use super::provider::{CredentialsFuture, CredentialsProvider};
+use super::{
+    Auth,
+    env::Env,
+    provider::{Credentials, CredentialsCache},
+    sts::StsClients,
+};
+use crate::{error::Error, types::sts::AssumeRoleWithOidcParams};
+
+pub(crate) const ROLE_ARN: &str = "ALIBABA_CLOUD_ROLE_ARN";
+pub(crate) const OIDC_PROVIDER_ARN: &str = "ALIBABA_CLOUD_OIDC_PROVIDER_ARN";
+pub(crate) const OIDC_TOKEN_FILE: &str = "ALIBABA_CLOUD_OIDC_TOKEN_FILE";
+const ROLE_SESSION_NAME: &str = "ALIBABA_CLOUD_ROLE_SESSION_NAME";
+const PROVIDER_NAME: &str = "oidc_role_arn";
+
+/// Exchanges an OIDC token file for STS credentials with `AssumeRoleWithOIDC`.
+///
+/// This is how pods on ACK use RRSA: the cluster projects a service account token into
+/// the pod and sets `ALIBABA_CLOUD_ROLE_ARN`, `ALIBABA_CLOUD_OIDC_PROVIDER_ARN` and
+/// `ALIBABA_CLOUD_OIDC_TOKEN_FILE`; see [`from_env`](Self::from_env).
+///
+/// The token file is re-read on every refresh because the kubelet rotates it. The STS
+/// call is not signed. Credentials are cached until shortly before they expire.
+#[derive(Debug)]
+pub struct OidcRoleArnProvider {
+    role_arn: String,
+    oidc_provider_arn: String,
+    oidc_token_file: PathBuf,
+    role_session_name: Option<String>,
+    policy: Option<String>,
+    duration_seconds: Option<u32>,
+    sts: StsClients,
+    cached: CredentialsCache,
+}
+
+impl OidcRoleArnProvider {
+    /// The STS endpoint follows `ALIBABA_CLOUD_STS_REGION` and
+    /// `ALIBABA_CLOUD_VPC_ENDPOINT_ENABLED`; override it with
+    /// [`sts_endpoint`](Self::sts_endpoint).
+    pub fn new(
+        role_arn: impl Into<String>,
+        oidc_provider_arn: impl Into<String>,
+        oidc_token_file: impl Into<PathBuf>,
+    ) -> Self {
+        Self::with_sts_endpoint(
+            role_arn.into(),
+            oidc_provider_arn.into(),
+            oidc_token_file.into(),
+            Env::real().sts_endpoint(),
+        )
+    }
+
+    /// Build the provider from the RRSA environment variables.
+    pub fn from_env() -> Result<Self, Error> {
+        Self::with_env(&Env::real()).ok_or_else(|| {
+            Error::auth(format!(
+                "{ROLE_ARN}, {OIDC_PROVIDER_ARN} or {OIDC_TOKEN_FILE} is not set"
+            ))
+        })
+    }
+
+    /// Build the provider from the RRSA environment variables, if all of them are set.
+    pub(crate) fn with_env(env: &Env) -> Option<Self> {
+        let mut provider = Self::with_sts_endpoint(
+            env.get(ROLE_ARN)?,
+            env.get(OIDC_PROVIDER_ARN)?,
+            PathBuf::from(env.get(OIDC_TOKEN_FILE)?),
+            env.sts_endpoint(),
+        );
+        provider.role_session_name = env.get(ROLE_SESSION_NAME);
+        Some(provider)
+    }
+
+    pub(crate) fn with_sts_endpoint(
+        role_arn: String,
+        oidc_provider_arn: String,
+        oidc_token_file: PathBuf,
+        sts_endpoint: String,
+    ) -> Self {
+        Self {
+            role_arn,
+            oidc_provider_arn,
+            oidc_token_file,
+            role_session_name: None,
+            policy: None,
+            duration_seconds: None,
+            sts: StsClients::new(sts_endpoint, Auth::none()),
+            cached: CredentialsCache::new(),
+        }
+    }
+
+    /// Session name (default `alibabacloud-rust-<unix time>`).
+    pub fn role_session_name(mut self, role_session_name: impl Into<String>) -> Self {
+        self.role_session_name = Some(role_session_name.into());
+        self
+    }
+
+    /// Inline policy that further restricts the session.
+    pub fn policy(mut self, policy: impl Into<String>) -> Self {
+        self.policy = Some(policy.into());
+        self
+    }
+
+    pub fn duration_seconds(mut self, duration_seconds: u32) -> Self {
+        self.duration_seconds = Some(duration_seconds);
+        self
+    }
+
+    pub fn sts_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.sts = StsClients::new(endpoint, Auth::none());
+        self
+    }
+
+    pub(crate) fn invalidate_cache(&self) {
+        self.cached.clear();
+    }
+
+    #[cfg(feature = "async")]
+    pub(crate) async fn fetch(&self) -> Result<Credentials, Error> {
+        if let Some(credentials) = self.cached.fresh() {
+            return Ok(credentials);
+        }
+        let response = self
+            .sts
+            .sts()?
+            .assume_role_with_oidc(self.params()?)
+            .await?;
+        let credentials = Credentials::try_from(response.credentials)?;
+        Ok(self
+            .cached
+            .insert(credentials.with_provider_name(PROVIDER_NAME)))
+    }
+
+    #[cfg(feature = "blocking")]
+    pub(crate) fn fetch_blocking(&self) -> Result<Credentials, Error> {
+        if let Some(credentials) = self.cached.fresh() {
+            return Ok(credentials);
+        }
+        let response = self
+            .sts
+            .blocking_sts()?
+            .assume_role_with_oidc(self.params()?)?;
+        let credentials = Credentials::try_from(response.credentials)?;
+        Ok(self
+            .cached
+            .insert(credentials.with_provider_name(PROVIDER_NAME)))
+    }
+
+    fn params(&self) -> Result<AssumeRoleWithOidcParams, Error> {
+        let token = std::fs::read_to_string(&self.oidc_token_file).map_err(|e| {
+            Error::auth(format!(
+                "failed to read OIDC token file {}: {e}",
+                self.oidc_token_file.display()
+            ))
+        })?;
+
+        Ok(AssumeRoleWithOidcParams {
+            role_arn: self.role_arn.clone(),
+            oidc_provider_arn: self.oidc_provider_arn.clone(),
+            oidc_token: token.trim().to_owned(),
+            role_session_name: Some(
+                self.role_session_name
+                    .clone()
+                    .unwrap_or_else(default_session_name),
+            ),
+            policy: self.policy.clone(),
+            duration_seconds: self.duration_seconds,
+        })
+    }
+}
+
+#[cfg(feature = "async")]
+impl CredentialsProvider for OidcRoleArnProvider {
+    fn provide_credentials(&self) -> CredentialsFuture<'_> {
+        Box::pin(self.fetch())
+    }
+
+    fn invalidate(&self) {
+        self.invalidate_cache();
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl BlockingCredentialsProvider for OidcRoleArnProvider {
+    fn provide_credentials_blocking(&self) -> Result<Credentials, Error> {
+        self.fetch_blocking()
+    }
+
+    fn invalidate(&self) {
+        self.invalidate_cache();
+    }
+}
+
+pub(crate) fn default_session_name() -> String {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default();
+    format!("alibabacloud-rust-{now}")
+}
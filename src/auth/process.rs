@@ -0,0 +1,415 @@
+use std::{
+    ffi::{OsStr, OsString},
+    fmt,
+    io::Read,
+    process::{Child, Command, ExitStatus, Stdio},
+    sync::{
+        Arc, Mutex, PoisonError,
+        mpsc::{self, RecvTimeoutError, Sender},
+    },
+    time::{Duration, Instant},
+};
+
+#[cfg(feature = "blocking")]
+use super::provider::BlockingCredentialsProvider;
+#[cfg(feature = "async")]
+use super::provider::{CredentialsFuture, CredentialsProvider};
+use zeroize::Zeroizing;
+
+use super::{
+    AccessKey, SecretString,
+    provider::{Credentials, CredentialsCache, parse_expiration},
+};
+use crate::error::Error;
+
+const PROVIDER_NAME: &str = "credential_process";
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+const STDERR_MAX_LEN: usize = 1024;
+/// Credentials JSON is far smaller; more output is an error rather than unbounded memory.
+const STDOUT_MAX_BYTES: usize = 64 * 1024;
+/// Enough for [`STDERR_MAX_LEN`] characters of any width.
+const STDERR_MAX_BYTES: usize = STDERR_MAX_LEN * 4;
+
+/// Runs an external command that prints credentials as JSON on stdout, like
+/// `credential_process` in other cloud SDKs.
+///
+/// The output may use the SDK field names
+/// (`{"AccessKeyId", "AccessKeySecret", "SecurityToken", "Expiration"}`) or the `aliyun`
+/// CLI external-mode names (`access_key_id`, `access_key_secret`, `sts_token`).
+/// `Expiration` is optional; without it the credentials are used until the process exits.
+///
+/// The command is killed when it runs longer than [`timeout`](Self::timeout) (60 seconds
+/// by default). Its stderr is captured and included in errors. Credentials are cached and
+/// the command runs again shortly before they expire.
+pub struct ProcessCredentialsProvider {
+    program: OsString,
+    args: Vec<OsString>,
+    timeout: Duration,
+    cached: CredentialsCache,
+}
+
+impl ProcessCredentialsProvider {
+    pub fn new(program: impl Into<OsString>) -> Self {
+        Self {
+            program: program.into(),
+            args: Vec::new(),
+            timeout: DEFAULT_TIMEOUT,
+            cached: CredentialsCache::new(),
+        }
+    }
+
+    /// Split a command line on whitespace (no shell quoting), as the `aliyun` CLI does for
+    /// `process_command`.
+    pub fn from_command_line(command_line: &str) -> Result<Self, Error> {
+        let mut parts = command_line.split_whitespace();
+        let program = parts
+            .next()
+            .ok_or_else(|| Error::invalid_config("credential process command is empty", None))?;
+        Ok(Self::new(program).args(parts))
+    }
+
+    pub fn arg(mut self, arg: impl Into<OsString>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<OsString>,
+    {
+        self.args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub(crate) fn invalidate_cache(&self) {
+        self.cached.clear();
+    }
+
+    #[cfg(feature = "async")]
+    pub(crate) async fn fetch(&self) -> Result<Credentials, Error> {
+        if let Some(credentials) = self.cached.fresh() {
+            return Ok(credentials);
+        }
+        let (program, args, timeout) = (self.program.clone(), self.args.clone(), self.timeout);
+        let credentials = tokio::task::spawn_blocking(move || run(&program, &args, timeout))
+            .await
+            .map_err(|e| Error::auth(format!("credential process task failed: {e}")))??;
+        Ok(self.cached.insert(credentials))
+    }
+
+    #[cfg(feature = "blocking")]
+    pub(crate) fn fetch_blocking(&self) -> Result<Credentials, Error> {
+        if let Some(credentials) = self.cached.fresh() {
+            return Ok(credentials);
+        }
+        let credentials = run(&self.program, &self.args, self.timeout)?;
+        Ok(self.cached.insert(credentials))
+    }
+}
+
+impl fmt::Debug for ProcessCredentialsProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProcessCredentialsProvider")
+            .field("program", &self.program)
+            .field("args", &self.args)
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+#[cfg(feature = "async")]
+impl CredentialsProvider for ProcessCredentialsProvider {
+    fn provide_credentials(&self) -> CredentialsFuture<'_> {
+        Box::pin(self.fetch())
+    }
+
+    fn invalidate(&self) {
+        self.invalidate_cache();
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl BlockingCredentialsProvider for ProcessCredentialsProvider {
+    fn provide_credentials_blocking(&self) -> Result<Credentials, Error> {
+        self.fetch_blocking()
+    }
+
+    fn invalidate(&self) {
+        self.invalidate_cache();
+    }
+}
+
+fn run(program: &OsStr, args: &[OsString], timeout: Duration) -> Result<Credentials, Error> {
+    let name = program.to_string_lossy();
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| Error::auth(format!("failed to run credential process {name}: {e}")))?;
+    let (eof_tx, eof_rx) = mpsc::channel::<()>();
+    let stdout = PipeReader::spawn(child.stdout.take(), STDOUT_MAX_BYTES, eof_tx.clone());
+    let stderr = PipeReader::spawn(child.stderr.take(), STDERR_MAX_BYTES, eof_tx);
+
+    // The pipes close when the process exits, or only later if descendants inherited
+    // them, so wait for end of file no longer than the deadline.
+    let deadline = Instant::now() + timeout;
+    let closed = matches!(
+        eof_rx.recv_timeout(deadline.saturating_duration_since(Instant::now())),
+        Err(RecvTimeoutError::Disconnected)
+    );
+    let status = if closed {
+        wait_until(&mut child, deadline)
+    } else {
+        Ok(None)
+    };
+    let status = match status {
+        Ok(Some(status)) => status,
+        Ok(None) => {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(Error::auth(format!(
+                "credential process {name} timed out after {timeout:?}{}",
+                stderr_suffix(&stderr.take().bytes)
+            )));
+        }
+        Err(e) => {
+            return Err(Error::auth(format!(
+                "failed to wait for credential process {name}: {e}"
+            )));
+        }
+    };
+
+    let stdout = stdout.take();
+    let stderr = stderr.take().bytes;
+    if !status.success() {
+        return Err(Error::auth(format!(
+            "credential process {name} exited with {status}{}",
+            stderr_suffix(&stderr)
+        )));
+    }
+    #[cfg(feature = "tracing")]
+    if !stderr.is_empty() {
+        tracing::debug!(stderr = %truncated_stderr(&stderr), "credential process wrote to stderr");
+    }
+    if stdout.truncated {
+        return Err(Error::auth(format!(
+            "credential process {name} printed more than {STDOUT_MAX_BYTES} bytes"
+        )));
+    }
+    parse_output(&stdout.bytes)
+}
+
+/// Reap a process that has closed its output; it normally exits right after, so this
+/// rarely has to wait.
+fn wait_until(child: &mut Child, deadline: Instant) -> std::io::Result<Option<ExitStatus>> {
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        if Instant::now() >= deadline {
+            return Ok(None);
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// A pipe drained on a background thread so a chatty process cannot fill it and stall.
+///
+/// At most `limit` bytes are kept, in a buffer allocated up front and zeroed on drop
+/// since stdout holds the secret. The thread drops its `eof` sender at end of file. It is
+/// never joined, since a descendant of the process may keep the pipe open after the
+/// deadline.
+struct PipeReader {
+    output: Arc<Mutex<Output>>,
+}
+
+#[derive(Default)]
+struct Output {
+    bytes: Zeroizing<Vec<u8>>,
+    /// Whether the pipe had more than the limit.
+    truncated: bool,
+}
+
+impl PipeReader {
+    fn spawn<R>(pipe: Option<R>, limit: usize, eof: Sender<()>) -> Self
+    where
+        R: Read + Send + 'static,
+    {
+        let output = Arc::new(Mutex::new(Output::default()));
+        if let Some(mut pipe) = pipe {
+            let output = Arc::clone(&output);
+            std::thread::spawn(move || {
+                output
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .bytes
+                    .reserve_exact(limit);
+                let mut chunk = Zeroizing::new([0u8; 4096]);
+                while let Ok(n @ 1..) = pipe.read(&mut chunk[..]) {
+                    let mut output = output.lock().unwrap_or_else(PoisonError::into_inner);
+                    let room = limit - output.bytes.len();
+                    output.truncated |= n > room;
+                    output.bytes.extend_from_slice(&chunk[..n.min(room)]);
+                }
+                drop(eof);
+            });
+        }
+        Self { output }
+    }
+
+    /// Move out what was read so far.
+    fn take(&self) -> Output {
+        std::mem::take(&mut *self.output.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+}
+
+fn truncated_stderr(stderr: &[u8]) -> String {
+    let text = String::from_utf8_lossy(stderr);
+    let text = text.trim();
+    match text.char_indices().nth(STDERR_MAX_LEN) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text.to_owned(),
+    }
+}
+
+fn stderr_suffix(stderr: &[u8]) -> String {
+    let text = truncated_stderr(stderr);
+    if text.is_empty() {
+        String::new()
+    } else {
+        format!("; stderr: {text}")
+    }
+}
+
+/// Parse the command output. Errors never echo the output, which holds the secret.
+fn parse_output(stdout: &[u8]) -> Result<Credentials, Error> {
+    #[derive(serde::Deserialize)]
+    struct ProcessOutput {
+        #[serde(alias = "AccessKeyId")]
+        access_key_id: String,
+        #[serde(alias = "AccessKeySecret")]
+        access_key_secret: SecretString,
+        #[serde(default, alias = "SecurityToken", alias = "sts_token")]
+        security_token: Option<SecretString>,
+        #[serde(default, alias = "Expiration")]
+        expiration: Option<String>,
+    }
+
+    let output: ProcessOutput = serde_json::from_slice(stdout).map_err(|e| {
+        Error::auth(format!(
+            "credential process printed invalid credentials JSON ({:?} error at line {}, column {})",
+            e.classify(),
+            e.line(),
+            e.column()
+        ))
+    })?;
+
+    let mut access_key = AccessKey::from_secret(output.access_key_id, output.access_key_secret);
+    access_key.security_token = output
+        .security_token
+        .filter(|token| !token.expose_secret().is_empty());
+    let mut credentials = Credentials::from(access_key).with_provider_name(PROVIDER_NAME);
+    if let Some(expiration) = output.expiration.as_deref() {
+        credentials = credentials.with_expiration(parse_expiration(expiration)?);
+    }
+    Ok(credentials)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn sh(script: &str, timeout: Duration) -> Result<Credentials, Error> {
+        run(
+            OsStr::new("sh"),
+            &[OsString::from("-c"), OsString::from(script)],
+            timeout,
+        )
+    }
+
+    #[test]
+    fn parses_sdk_and_cli_output() {
+        let credentials = sh(
+            r#"echo '{"AccessKeyId":"id","AccessKeySecret":"secret","SecurityToken":"token","Expiration":"2099-01-01T00:00:00Z"}'"#,
+            DEFAULT_TIMEOUT,
+        )
+        .unwrap();
+        assert_eq!(credentials.access_key_id(), "id");
+        assert!(credentials.access_key().has_security_token());
+        assert!(credentials.expiration().is_some());
+        assert_eq!(credentials.provider_name(), Some("credential_process"));
+
+        let credentials = sh(
+            r#"echo '{"mode":"StsToken","access_key_id":"cli-id","access_key_secret":"s","sts_token":"t"}'"#,
+            DEFAULT_TIMEOUT,
+        )
+        .unwrap();
+        assert_eq!(credentials.access_key_id(), "cli-id");
+        assert!(credentials.expiration().is_none());
+    }
+
+    #[test]
+    fn failure_reports_exit_status_and_stderr() {
+        let err = sh("echo 'vault is sealed' >&2; exit 3", DEFAULT_TIMEOUT).unwrap_err();
+        assert!(err.is_auth_error());
+        let message = err.message().unwrap();
+        assert!(message.contains("exit status: 3"), "{message}");
+        assert!(message.contains("vault is sealed"), "{message}");
+    }
+
+    #[test]
+    fn slow_process_is_killed_after_timeout() {
+        let started = Instant::now();
+        let err = sh("echo waiting >&2; sleep 5", Duration::from_millis(200)).unwrap_err();
+        assert!(started.elapsed() < Duration::from_secs(4));
+        let message = err.message().unwrap();
+        assert!(message.contains("timed out"), "{message}");
+        assert!(message.contains("waiting"), "{message}");
+    }
+
+    #[test]
+    fn background_descendant_holding_stdout_does_not_hang() {
+        let started = Instant::now();
+        let err = sh(
+            r#"echo '{"AccessKeyId":"id","AccessKeySecret":"secret"}'; sleep 5 &"#,
+            Duration::from_millis(300),
+        )
+        .unwrap_err();
+        assert!(started.elapsed() < Duration::from_secs(4));
+        assert!(err.message().unwrap().contains("timed out"), "{err}");
+    }
+
+    #[test]
+    fn oversized_output_is_rejected() {
+        let err = sh("head -c 1000000 /dev/zero | tr '\\0' x", DEFAULT_TIMEOUT).unwrap_err();
+        let message = err.message().unwrap();
+        assert!(
+            message.contains("printed more than 65536 bytes"),
+            "{message}"
+        );
+    }
+
+    #[test]
+    fn invalid_output_does_not_echo_secrets() {
+        let err = sh(
+            r#"echo '{"AccessKeyId":"id","AccessKeySecret":42,"Leaked":"hunter2"}'"#,
+            DEFAULT_TIMEOUT,
+        )
+        .unwrap_err();
+        let message = err.message().unwrap();
+        assert!(message.contains("invalid credentials JSON"), "{message}");
+        assert!(
+            !message.contains("hunter2") && !message.contains("42"),
+            "{message}"
+        );
+    }
+}
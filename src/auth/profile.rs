@@ -0,0 +1,200 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+#[cfg(feature = "blocking")]
+use super::provider::BlockingCredentialsProvider;
+#[cfg(feature = "async")]
+use super::provider::{CredentialsFuture, CredentialsProvider};
+use super::{env::Env, provider::Credentials};
+use crate::error::Error;
+
+const CREDENTIALS_FILE: &str = "ALIBABA_CLOUD_CREDENTIALS_FILE";
+const DEFAULT_PROFILE: &str = "default";
+const PROVIDER_NAME: &str = "profile";
+
+/// Reads access keys from the shared credentials file.
+///
+/// The file is `ALIBABA_CLOUD_CREDENTIALS_FILE` when set, otherwise
+/// `~/.alibabacloud/credentials.ini`, in the INI format used by the official SDKs:
+///
+/// ```ini
+/// [default]
+/// type = access_key
+/// access_key_id = foo
+/// access_key_secret = bar
+/// ```
+#[derive(Debug, Clone)]
+pub struct ProfileFileProvider {
+    path: Option<PathBuf>,
+    profile: String,
+    env: Env,
+}
+
+impl Default for ProfileFileProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProfileFileProvider {
+    pub fn new() -> Self {
+        Self::with_env(Env::real())
+    }
+
+    pub(crate) fn with_env(env: Env) -> Self {
+        Self {
+            path: None,
+            profile: DEFAULT_PROFILE.to_owned(),
+            env,
+        }
+    }
+
+    /// Read this file instead of the default location.
+    pub fn path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    /// Select a profile section other than `default`.
+    pub fn profile(mut self, profile: impl Into<String>) -> Self {
+        self.profile = profile.into();
+        self
+    }
+
+    pub(crate) fn load(&self) -> Result<Credentials, Error> {
+        let path = self.resolve_path()?;
+        let text = std::fs::read_to_string(&path).map_err(|e| {
+            Error::auth(format!(
+                "failed to read credentials file {}: {e}",
+                path.display()
+            ))
+        })?;
+
+        let sections = parse_ini(&text);
+        let section = sections.get(&self.profile).ok_or_else(|| {
+            Error::auth(format!(
+                "profile {} not found in {}",
+                self.profile,
+                path.display()
+            ))
+        })?;
+
+        let kind = section
+            .get("type")
+            .map(String::as_str)
+            .unwrap_or("access_key");
+        match kind {
+            "access_key" => {
+                let field = |name: &str| {
+                    section.get(name).cloned().ok_or_else(|| {
+                        Error::auth(format!("profile {} is missing {name}", self.profile))
+                    })
+                };
+                Ok(
+                    Credentials::new(field("access_key_id")?, field("access_key_secret")?)
+                        .with_provider_name(PROVIDER_NAME),
+                )
+            }
+            other => Err(Error::auth(format!(
+                "profile {} uses unsupported type {other}",
+                self.profile
+            ))),
+        }
+    }
+
+    fn resolve_path(&self) -> Result<PathBuf, Error> {
+        if let Some(path) = &self.path {
+            return Ok(path.clone());
+        }
+        if let Some(path) = self.env.get(CREDENTIALS_FILE) {
+            return Ok(PathBuf::from(path));
+        }
+        self.env
+            .home_dir()
+            .map(|home| home.join(".alibabacloud").join("credentials.ini"))
+            .ok_or_else(|| Error::auth("cannot locate credentials file: home directory is unknown"))
+    }
+}
+
+#[cfg(feature = "async")]
+impl CredentialsProvider for ProfileFileProvider {
+    fn provide_credentials(&self) -> CredentialsFuture<'_> {
+        let result = self.load();
+        Box::pin(async move { result })
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl BlockingCredentialsProvider for ProfileFileProvider {
+    fn provide_credentials_blocking(&self) -> Result<Credentials, Error> {
+        self.load()
+    }
+}
+
+fn parse_ini(text: &str) -> BTreeMap<String, BTreeMap<String, String>> {
+    let mut sections: BTreeMap<String, BTreeMap<String, String>> = BTreeMap::new();
+    let mut current: Option<String> = None;
+
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(name) = line
+            .strip_prefix('[')
+            .and_then(|rest| rest.strip_suffix(']'))
+        {
+            let name = name.trim().to_owned();
+            sections.entry(name.clone()).or_default();
+            current = Some(name);
+            continue;
+        }
+        if let (Some(section), Some((key, value))) = (current.as_ref(), line.split_once('=')) {
+            let value = value.trim().trim_matches('"');
+            sections
+                .entry(section.clone())
+                .or_default()
+                .insert(key.trim().to_owned(), value.to_owned());
+        }
+    }
+
+    sections
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_sections_and_ignores_comments() {
+        let sections = parse_ini(
+            "# comment\n[default]\ntype = access_key\naccess_key_id = foo\n\n[other]\n; note\naccess_key_id=\"bar\"\n",
+        );
+        assert_eq!(sections["default"]["access_key_id"], "foo");
+        assert_eq!(sections["other"]["access_key_id"], "bar");
+    }
+
+    #[test]
+    fn loads_access_key_profile_from_file() {
+        let path = std::env::temp_dir().join(format!(
+            "alibabacloud-credentials-{}.ini",
+            uuid::Uuid::new_v4()
+        ));
+        std::fs::write(
+            &path,
+            "[default]\ntype = access_key\naccess_key_id = default-id\naccess_key_secret = s\n\n[dev]\naccess_key_id = dev-id\naccess_key_secret = s\n",
+        )
+        .unwrap();
+
+        let env = Env::from_pairs(&[(CREDENTIALS_FILE, path.to_str().unwrap())]);
+        let default = ProfileFileProvider::with_env(env.clone()).load().unwrap();
+        let dev = ProfileFileProvider::with_env(env)
+            .profile("dev")
+            .load()
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(default.access_key_id(), "default-id");
+        assert_eq!(dev.access_key_id(), "dev-id");
+        assert_eq!(dev.provider_name(), Some("profile"));
+    }
+}
@@ -0,0 +1,437 @@
+use std::{
+    fmt,
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, SystemTime},
+};
+
+#[cfg(feature = "async")]
+use std::{future::Future, pin::Pin};
+
+use crate::error::Error;
+
+use super::{AccessKey, Auth};
+
+/// Cached credentials are refreshed when they expire within this window.
+const REFRESH_BUFFER: Duration = Duration::from_secs(180);
+
+/// Credentials returned by a credentials provider.
+#[derive(Debug, Clone)]
+pub struct Credentials {
+    access_key: AccessKey,
+    expiration: Option<SystemTime>,
+    provider_name: Option<&'static str>,
+}
+
+impl Credentials {
+    /// Create long-lived credentials from an access key pair.
+    pub fn new(access_key_id: impl Into<String>, access_key_secret: impl Into<String>) -> Self {
+        Self::from(AccessKey::new(access_key_id, access_key_secret))
+    }
+
+    /// Attach an STS security token.
+    pub fn with_security_token(mut self, security_token: impl Into<String>) -> Self {
+        self.access_key = self.access_key.with_security_token(security_token);
+        self
+    }
+
+    /// Set the point in time after which the credentials are no longer valid.
+    pub fn with_expiration(mut self, expiration: SystemTime) -> Self {
+        self.expiration = Some(expiration);
+        self
+    }
+
+    /// Record which provider produced the credentials (for diagnostics).
+    pub fn with_provider_name(mut self, provider_name: &'static str) -> Self {
+        self.provider_name = Some(provider_name);
+        self
+    }
+
+    /// The access key pair and security token.
+    pub fn access_key(&self) -> &AccessKey {
+        &self.access_key
+    }
+
+    /// The access key ID.
+    pub fn access_key_id(&self) -> &str {
+        self.access_key.access_key_id()
+    }
+
+    /// When the credentials stop being valid; `None` for long-lived credentials.
+    pub fn expiration(&self) -> Option<SystemTime> {
+        self.expiration
+    }
+
+    /// Name of the provider that produced the credentials, if it recorded one.
+    pub fn provider_name(&self) -> Option<&'static str> {
+        self.provider_name
+    }
+
+    pub(crate) fn into_access_key(self) -> AccessKey {
+        self.access_key
+    }
+
+    fn expires_within(&self, window: Duration) -> bool {
+        match self.expiration {
+            Some(expiration) => SystemTime::now() + window >= expiration,
+            None => false,
+        }
+    }
+}
+
+impl From<AccessKey> for Credentials {
+    fn from(access_key: AccessKey) -> Self {
+        Self {
+            access_key,
+            expiration: None,
+            provider_name: None,
+        }
+    }
+}
+
+/// STS-style credentials payload shared by the metadata service, STS and credential endpoints.
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub(crate) struct SessionCredentials {
+    access_key_id: String,
+    access_key_secret: String,
+    #[serde(default)]
+    security_token: Option<String>,
+    #[serde(default)]
+    expiration: Option<String>,
+}
+
+impl SessionCredentials {
+    pub(crate) fn into_credentials(
+        self,
+        provider_name: &'static str,
+    ) -> Result<Credentials, Error> {
+        let mut credentials = Credentials::new(self.access_key_id, self.access_key_secret)
+            .with_provider_name(provider_name);
+        if let Some(token) = self.security_token.filter(|token| !token.is_empty()) {
+            credentials = credentials.with_security_token(token);
+        }
+        if let Some(expiration) = self.expiration.as_deref() {
+            credentials = credentials.with_expiration(parse_expiration(expiration)?);
+        }
+        Ok(credentials)
+    }
+}
+
+/// Parse an RFC 3339 expiration timestamp such as `2024-01-01T00:00:00Z`.
+pub(crate) fn parse_expiration(value: &str) -> Result<SystemTime, Error> {
+    let parsed =
+        time::OffsetDateTime::parse(value.trim(), &time::format_description::well_known::Rfc3339)
+            .map_err(|e| Error::auth(format!("invalid credentials expiration {value:?}: {e}")))?;
+    Ok(SystemTime::from(parsed))
+}
+
+/// Boxed future returned by [`CredentialsProvider::provide_credentials`].
+#[cfg(feature = "async")]
+pub type CredentialsFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<Credentials, Error>> + Send + 'a>>;
+
+/// Async source of credentials for [`Client`](crate::Client).
+///
+/// The client caches the returned credentials and asks the provider again once
+/// they are about to expire, or after the server rejects their security token as expired.
+#[cfg(feature = "async")]
+pub trait CredentialsProvider: fmt::Debug + Send + Sync {
+    /// Fetch credentials; called only when the client has no fresh ones cached.
+    fn provide_credentials(&self) -> CredentialsFuture<'_>;
+
+    /// Forget any cached credentials so the next call fetches new ones.
+    ///
+    /// Called when the server rejects the security token as expired. The default does
+    /// nothing.
+    fn invalidate(&self) {}
+}
+
+/// Blocking source of credentials for [`BlockingClient`](crate::BlockingClient).
+///
+/// The client caches the returned credentials and asks the provider again once
+/// they are about to expire, or after the server rejects their security token as expired.
+#[cfg(feature = "blocking")]
+pub trait BlockingCredentialsProvider: fmt::Debug + Send + Sync {
+    /// Fetch credentials; called only when the client has no fresh ones cached.
+    fn provide_credentials_blocking(&self) -> Result<Credentials, Error>;
+
+    /// Forget any cached credentials so the next call fetches new ones.
+    ///
+    /// Called when the server rejects the security token as expired. The default does
+    /// nothing.
+    fn invalidate(&self) {}
+}
+
+/// A credentials provider that can be shared between clients.
+#[derive(Clone)]
+pub struct SharedCredentialsProvider {
+    #[cfg(feature = "async")]
+    async_provider: Option<Arc<dyn CredentialsProvider>>,
+    #[cfg(feature = "blocking")]
+    blocking_provider: Option<Arc<dyn BlockingCredentialsProvider>>,
+}
+
+impl SharedCredentialsProvider {
+    /// Share an async provider; a `BlockingClient` using it fails at request time.
+    #[cfg(feature = "async")]
+    pub fn new(provider: impl CredentialsProvider + 'static) -> Self {
+        Self {
+            async_provider: Some(Arc::new(provider)),
+            #[cfg(feature = "blocking")]
+            blocking_provider: None,
+        }
+    }
+
+    /// Share a blocking provider; a `Client` using it fails at request time.
+    #[cfg(feature = "blocking")]
+    pub fn new_blocking(provider: impl BlockingCredentialsProvider + 'static) -> Self {
+        Self {
+            #[cfg(feature = "async")]
+            async_provider: None,
+            blocking_provider: Some(Arc::new(provider)),
+        }
+    }
+
+    /// Share a built-in provider that implements every enabled client flavour.
+    #[cfg(all(feature = "async", feature = "blocking"))]
+    pub(crate) fn from_builtin<P>(provider: P) -> Self
+    where
+        P: CredentialsProvider + BlockingCredentialsProvider + 'static,
+    {
+        let provider = Arc::new(provider);
+        Self {
+            async_provider: Some(provider.clone()),
+            blocking_provider: Some(provider),
+        }
+    }
+
+    #[cfg(all(feature = "async", not(feature = "blocking")))]
+    pub(crate) fn from_builtin<P>(provider: P) -> Self
+    where
+        P: CredentialsProvider + 'static,
+    {
+        Self::new(provider)
+    }
+
+    #[cfg(all(feature = "blocking", not(feature = "async")))]
+    pub(crate) fn from_builtin<P>(provider: P) -> Self
+    where
+        P: BlockingCredentialsProvider + 'static,
+    {
+        Self::new_blocking(provider)
+    }
+
+    #[cfg(feature = "async")]
+    pub(crate) fn async_provider(&self) -> Option<&dyn CredentialsProvider> {
+        self.async_provider.as_deref()
+    }
+
+    #[cfg(feature = "blocking")]
+    pub(crate) fn blocking_provider(&self) -> Option<&dyn BlockingCredentialsProvider> {
+        self.blocking_provider.as_deref()
+    }
+}
+
+impl fmt::Debug for SharedCredentialsProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("SharedCredentialsProvider");
+        #[cfg(feature = "async")]
+        debug.field("async_provider", &self.async_provider);
+        #[cfg(feature = "blocking")]
+        debug.field("blocking_provider", &self.blocking_provider);
+        debug.finish()
+    }
+}
+
+/// Cache of provider credentials, held per client and by refreshing providers.
+///
+/// Refreshes in [`resolve`](Self::resolve) and [`resolve_blocking`](Self::resolve_blocking)
+/// are single-flight: concurrent callers wait for the one in progress and reuse its result.
+#[derive(Debug)]
+pub(crate) struct CredentialsCache {
+    cached: Mutex<Option<Credentials>>,
+    #[cfg(feature = "async")]
+    refresh: tokio::sync::Mutex<()>,
+    #[cfg(feature = "blocking")]
+    refresh_blocking: Mutex<()>,
+}
+
+impl CredentialsCache {
+    pub(crate) fn new() -> Self {
+        Self {
+            cached: Mutex::new(None),
+            #[cfg(feature = "async")]
+            refresh: tokio::sync::Mutex::new(()),
+            #[cfg(feature = "blocking")]
+            refresh_blocking: Mutex::new(()),
+        }
+    }
+
+    /// Resolve the access key used to sign the next request.
+    #[cfg(feature = "async")]
+    pub(crate) async fn resolve(&self, auth: &Auth) -> Result<AccessKey, Error> {
+        match auth {
+            Auth::None => Err(access_key_required()),
+            Auth::AccessKey(access_key) => Ok(access_key.clone()),
+            Auth::Provider(shared) => {
+                if let Some(credentials) = self.fresh() {
+                    return Ok(credentials.into_access_key());
+                }
+                let _refresh = self.refresh.lock().await;
+                if let Some(credentials) = self.fresh() {
+                    return Ok(credentials.into_access_key());
+                }
+                let provider = shared.async_provider().ok_or_else(|| {
+                    Error::invalid_config(
+                        "credentials provider does not support the async client",
+                        None,
+                    )
+                })?;
+                let credentials = provider.provide_credentials().await?;
+                Ok(self.store(credentials))
+            }
+        }
+    }
+
+    /// Resolve the access key used to sign the next request.
+    #[cfg(feature = "blocking")]
+    pub(crate) fn resolve_blocking(&self, auth: &Auth) -> Result<AccessKey, Error> {
+        match auth {
+            Auth::None => Err(access_key_required()),
+            Auth::AccessKey(access_key) => Ok(access_key.clone()),
+            Auth::Provider(shared) => {
+                if let Some(credentials) = self.fresh() {
+                    return Ok(credentials.into_access_key());
+                }
+                let _refresh = self
+                    .refresh_blocking
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner);
+                if let Some(credentials) = self.fresh() {
+                    return Ok(credentials.into_access_key());
+                }
+                let provider = shared.blocking_provider().ok_or_else(|| {
+                    Error::invalid_config(
+                        "credentials provider does not support the blocking client",
+                        None,
+                    )
+                })?;
+                let credentials = provider.provide_credentials_blocking()?;
+                Ok(self.store(credentials))
+            }
+        }
+    }
+
+    /// Cached credentials that are not about to expire.
+    pub(crate) fn fresh(&self) -> Option<Credentials> {
+        let cached = self.cached.lock().unwrap_or_else(PoisonError::into_inner);
+        cached
+            .as_ref()
+            .filter(|credentials| !credentials.expires_within(REFRESH_BUFFER))
+            .cloned()
+    }
+
+    pub(crate) fn clear(&self) {
+        *self.cached.lock().unwrap_or_else(PoisonError::into_inner) = None;
+    }
+
+    /// Drop the cached credentials, and those cached by a provider, after the server
+    /// rejected them. Returns whether new credentials can be fetched.
+    pub(crate) fn invalidate(&self, auth: &Auth) -> bool {
+        let Auth::Provider(shared) = auth else {
+            return false;
+        };
+        #[cfg(feature = "tracing")]
+        tracing::debug!("credentials rejected by the server; refreshing");
+        self.clear();
+        #[cfg(feature = "async")]
+        if let Some(provider) = shared.async_provider() {
+            provider.invalidate();
+        }
+        #[cfg(feature = "blocking")]
+        if let Some(provider) = shared.blocking_provider() {
+            provider.invalidate();
+        }
+        true
+    }
+
+    pub(crate) fn insert(&self, credentials: Credentials) -> Credentials {
+        *self.cached.lock().unwrap_or_else(PoisonError::into_inner) = Some(credentials.clone());
+        credentials
+    }
+
+    fn store(&self, credentials: Credentials) -> AccessKey {
+        let access_key = credentials.access_key.clone();
+        *self.cached.lock().unwrap_or_else(PoisonError::into_inner) = Some(credentials);
+        access_key
+    }
+}
+
+fn access_key_required() -> Error {
+    Error::invalid_config("access key authentication is required", None)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[derive(Debug, Default)]
+    struct SlowProvider {
+        calls: AtomicUsize,
+    }
+
+    impl SlowProvider {
+        fn credentials(&self) -> Credentials {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Credentials::new("id", "secret").with_security_token("token")
+        }
+    }
+
+    #[cfg(feature = "async")]
+    impl CredentialsProvider for Arc<SlowProvider> {
+        fn provide_credentials(&self) -> CredentialsFuture<'_> {
+            Box::pin(async move {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                Ok(self.credentials())
+            })
+        }
+    }
+
+    #[cfg(feature = "blocking")]
+    impl BlockingCredentialsProvider for Arc<SlowProvider> {
+        fn provide_credentials_blocking(&self) -> Result<Credentials, Error> {
+            std::thread::sleep(Duration::from_millis(50));
+            Ok(self.credentials())
+        }
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn concurrent_resolves_share_one_refresh() {
+        let provider = Arc::new(SlowProvider::default());
+        let auth = Auth::Provider(SharedCredentialsProvider::new(provider.clone()));
+        let cache = CredentialsCache::new();
+
+        let (first, second) = tokio::join!(cache.resolve(&auth), cache.resolve(&auth));
+        assert_eq!(first.unwrap().access_key_id(), "id");
+        assert_eq!(second.unwrap().access_key_id(), "id");
+        assert_eq!(provider.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn concurrent_blocking_resolves_share_one_refresh() {
+        let provider = Arc::new(SlowProvider::default());
+        let auth = Auth::Provider(SharedCredentialsProvider::new_blocking(provider.clone()));
+        let cache = CredentialsCache::new();
+
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| cache.resolve_blocking(&auth).unwrap());
+            }
+        });
+        assert_eq!(provider.calls.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,172 @@
+use std::{
+    fmt,
+    fs::File,
+    io::{ErrorKind, Read},
+    path::Path,
+};
+
+use serde::{Deserialize, Deserializer};
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::error::Error;
+
+/// Initial buffer size when the length of the secret is unknown.
+const MIN_READ_BUF: usize = 64;
+
+/// A secret such as an access key secret or security token.
+///
+/// It is redacted in `Debug` and `Display`, and its memory (including spare capacity) is
+/// zeroed when it is dropped. The value is only reachable through
+/// [`expose_secret`](Self::expose_secret).
+#[derive(Clone)]
+pub struct SecretString(String);
+
+impl SecretString {
+    pub fn new(value: impl Into<String>) -> Self {
+        Self(value.into())
+    }
+
+    /// Read a secret from a file, such as a mounted Kubernetes secret.
+    ///
+    /// A trailing line ending is removed.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let read_failed = |e: std::io::Error| {
+            Error::invalid_config(
+                format!("failed to read secret from {}", path.display()),
+                Some(Box::new(e)),
+            )
+        };
+        let file = File::open(path).map_err(read_failed)?;
+        let len = file.metadata().map_err(read_failed)?.len();
+        Self::read(file, usize::try_from(len).unwrap_or(0)).map_err(read_failed)
+    }
+
+    /// Read a secret from an open file descriptor (e.g. one passed by a parent process or
+    /// systemd credentials), which is closed afterwards.
+    ///
+    /// A trailing line ending is removed.
+    #[cfg(unix)]
+    pub fn from_fd(fd: std::os::fd::OwnedFd) -> Result<Self, Error> {
+        Self::from_reader(File::from(fd))
+    }
+
+    /// Read a secret from `reader` until EOF.
+    ///
+    /// A trailing line ending is removed.
+    pub fn from_reader(reader: impl Read) -> Result<Self, Error> {
+        Self::read(reader, 0)
+            .map_err(|e| Error::invalid_config("failed to read secret", Some(Box::new(e))))
+    }
+
+    /// The secret value, for signing requests.
+    ///
+    /// Avoid copying it into types that are not zeroed on drop.
+    pub fn expose_secret(&self) -> &str {
+        &self.0
+    }
+
+    /// Read straight into a buffer that is zeroed when it is replaced by a larger one or
+    /// dropped (on error, too), so no partial copies of the secret are left behind.
+    fn read(mut reader: impl Read, capacity: usize) -> std::io::Result<Self> {
+        // One spare byte lets the final read see EOF without growing the buffer.
+        let mut buf = Zeroizing::new(vec![0; capacity.max(MIN_READ_BUF).saturating_add(1)]);
+        let mut filled = 0;
+        loop {
+            if filled == buf.len() {
+                let mut larger = Zeroizing::new(vec![0; buf.len().saturating_mul(2)]);
+                larger[..filled].copy_from_slice(&buf[..filled]);
+                buf = larger;
+            }
+            match reader.read(&mut buf[filled..]) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        let text = std::str::from_utf8(&buf[..filled])
+            .map_err(|_| std::io::Error::new(ErrorKind::InvalidData, "secret is not UTF-8"))?;
+        Ok(Self(text.trim_end_matches(['\r', '\n']).to_owned()))
+    }
+}
+
+impl Drop for SecretString {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl From<String> for SecretString {
+    fn from(value: String) -> Self {
+        Self(value)
+    }
+}
+
+impl<'de> Deserialize<'de> for SecretString {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Self)
+    }
+}
+
+impl fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("<redacted>")
+    }
+}
+
+impl fmt::Display for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("<redacted>")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_file_trims_line_ending_and_redacts() {
+        let path =
+            std::env::temp_dir().join(format!("alibabacloud-secret-{}", uuid::Uuid::new_v4()));
+        std::fs::write(&path, "s3cr3t\r\n").unwrap();
+
+        let secret = SecretString::from_file(&path).unwrap();
+        assert_eq!(secret.expose_secret(), "s3cr3t");
+        assert_eq!(format!("{secret:?} {secret}"), "<redacted> <redacted>");
+        std::fs::remove_file(&path).unwrap();
+
+        let err = SecretString::from_file(&path).unwrap_err();
+        assert!(matches!(err, Error::InvalidConfig { .. }));
+        assert!(err.to_string().contains("failed to read secret"));
+    }
+
+    #[test]
+    fn from_reader_keeps_inner_whitespace() {
+        let secret = SecretString::from_reader(&b"a b\n\n"[..]).unwrap();
+        assert_eq!(secret.expose_secret(), "a b");
+    }
+
+    #[test]
+    fn from_reader_grows_past_the_initial_buffer() {
+        let value = "x".repeat(MIN_READ_BUF * 5 + 3);
+        let secret = SecretString::from_reader(value.as_bytes()).unwrap();
+        assert_eq!(secret.expose_secret(), value);
+
+        let err = SecretString::from_reader(&[0xff, 0xfe][..]).unwrap_err();
+        assert!(matches!(err, Error::InvalidConfig { .. }));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn from_fd_reads_descriptor() {
+        let path =
+            std::env::temp_dir().join(format!("alibabacloud-secret-{}", uuid::Uuid::new_v4()));
+        std::fs::write(&path, "token").unwrap();
+        let fd = std::os::fd::OwnedFd::from(File::open(&path).unwrap());
+
+        assert_eq!(SecretString::from_fd(fd).unwrap().expose_secret(), "token");
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,65 @@
+use std::sync::OnceLock;
+
+use super::Auth;
+use crate::error::Error;
+
+#[cfg(feature = "blocking")]
+use crate::{api::BlockingStsService, client::BlockingClient};
+#[cfg(feature = "async")]
+use crate::{api::StsService, client::Client};
+
+/// Lazily built STS clients for credentials providers that exchange credentials through STS.
+pub(crate) struct StsClients {
+    endpoint: String,
+    auth: Auth,
+    #[cfg(feature = "async")]
+    client: OnceLock<Client>,
+    #[cfg(feature = "blocking")]
+    blocking_client: OnceLock<BlockingClient>,
+}
+
+impl StsClients {
+    pub(crate) fn new(endpoint: impl Into<String>, auth: Auth) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            auth,
+            #[cfg(feature = "async")]
+            client: OnceLock::new(),
+            #[cfg(feature = "blocking")]
+            blocking_client: OnceLock::new(),
+        }
+    }
+
+    #[cfg(feature = "async")]
+    pub(crate) fn sts(&self) -> Result<StsService, Error> {
+        if let Some(client) = self.client.get() {
+            return Ok(client.sts());
+        }
+        let client = Client::builder()
+            .auth(self.auth.clone())
+            .sts_endpoint(&self.endpoint)
+            .build()?;
+        Ok(self.client.get_or_init(|| client).sts())
+    }
+
+    #[cfg(feature = "blocking")]
+    pub(crate) fn blocking_sts(&self) -> Result<BlockingStsService, Error> {
+        if let Some(client) = self.blocking_client.get() {
+            return Ok(client.sts());
+        }
+        let client = BlockingClient::builder()
+            .auth(self.auth.clone())
+            .sts_endpoint(&self.endpoint)
+            .build()?;
+        Ok(self.blocking_client.get_or_init(|| client).sts())
+    }
+}
+
+impl std::fmt::Debug for StsClients {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StsClients")
+            .field("endpoint", &self.endpoint)
+            .field("auth", &self.auth)
+            .finish()
+    }
+}
@@ -0,0 +1,188 @@
+use std::time::Duration;
+
+use http::{HeaderMap, Method};
+
+#[cfg(feature = "blocking")]
+use super::provider::BlockingCredentialsProvider;
+#[cfg(feature = "async")]
+use super::provider::{CredentialsFuture, CredentialsProvider};
+use super::{
+    env::Env,
+    http::CredentialsHttp,
+    provider::{Credentials, CredentialsCache, SessionCredentials},
+};
+use crate::{
+    error::Error,
+    transport::{Body, Request, Response},
+    util::redact,
+};
+
+pub(crate) const CREDENTIALS_URI: &str = "ALIBABA_CLOUD_CREDENTIALS_URI";
+const PROVIDER_NAME: &str = "credentials_uri";
+
+/// Fetches credentials from a local HTTP endpoint, such as a credentials sidecar.
+///
+/// The endpoint returns
+/// `{"AccessKeyId", "AccessKeySecret", "SecurityToken", "Expiration"}` JSON (an optional
+/// `"Code"` must be `"Success"`). Credentials are cached until shortly before they expire.
+///
+/// Failures are reported as [`Error::Auth`] with the URI reduced to its origin, since the
+/// path or query often carries a token.
+pub struct CredentialsUriProvider {
+    uri: String,
+    http: CredentialsHttp,
+    cached: CredentialsCache,
+}
+
+impl CredentialsUriProvider {
+    pub fn new(uri: impl Into<String>) -> Self {
+        Self {
+            uri: uri.into(),
+            http: CredentialsHttp::new(Duration::from_secs(5), Duration::from_secs(10)),
+            cached: CredentialsCache::new(),
+        }
+    }
+
+    /// Build the provider from `ALIBABA_CLOUD_CREDENTIALS_URI`.
+    pub fn from_env() -> Result<Self, Error> {
+        Self::with_env(&Env::real())
+            .ok_or_else(|| Error::auth(format!("{CREDENTIALS_URI} is not set")))
+    }
+
+    pub(crate) fn with_env(env: &Env) -> Option<Self> {
+        env.get(CREDENTIALS_URI).map(Self::new)
+    }
+
+    pub(crate) fn invalidate_cache(&self) {
+        self.cached.clear();
+    }
+
+    #[cfg(feature = "async")]
+    pub(crate) async fn fetch(&self) -> Result<Credentials, Error> {
+        if let Some(credentials) = self.cached.fresh() {
+            return Ok(credentials);
+        }
+        let (request, redacted) = self.request()?;
+        let response = self
+            .http
+            .send(request)
+            .await
+            .map_err(|e| fetch_failed(&redacted, &e))?;
+        Ok(self
+            .cached
+            .insert(parse_credentials(response, &redacted.redacted)?))
+    }
+
+    #[cfg(feature = "blocking")]
+    pub(crate) fn fetch_blocking(&self) -> Result<Credentials, Error> {
+        if let Some(credentials) = self.cached.fresh() {
+            return Ok(credentials);
+        }
+        let (request, redacted) = self.request()?;
+        let response = self
+            .http
+            .send_blocking(request)
+            .map_err(|e| fetch_failed(&redacted, &e))?;
+        Ok(self
+            .cached
+            .insert(parse_credentials(response, &redacted.redacted)?))
+    }
+
+    fn request(&self) -> Result<(Request, RedactedUri<'_>), Error> {
+        let url = url::Url::parse(&self.uri)
+            .map_err(|_| Error::auth(format!("invalid {CREDENTIALS_URI}")))?;
+        let redacted = RedactedUri {
+            raw: &self.uri,
+            redacted: redact::url(&url),
+            parsed: url.clone(),
+        };
+        let request = Request {
+            method: Method::GET,
+            url,
+            headers: HeaderMap::new(),
+            body: Body::empty(),
+            timeout: self.http.timeout(),
+        };
+        Ok((request, redacted))
+    }
+}
+
+impl std::fmt::Debug for CredentialsUriProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let uri = url::Url::parse(&self.uri)
+            .map(|url| redact::url(&url))
+            .unwrap_or_else(|_| "<redacted>".to_owned());
+        f.debug_struct("CredentialsUriProvider")
+            .field("uri", &uri)
+            .finish()
+    }
+}
+
+/// The configured URI and the form that may appear in errors.
+struct RedactedUri<'a> {
+    raw: &'a str,
+    parsed: url::Url,
+    redacted: String,
+}
+
+#[cfg(feature = "async")]
+impl CredentialsProvider for CredentialsUriProvider {
+    fn provide_credentials(&self) -> CredentialsFuture<'_> {
+        Box::pin(self.fetch())
+    }
+
+    fn invalidate(&self) {
+        self.invalidate_cache();
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl BlockingCredentialsProvider for CredentialsUriProvider {
+    fn provide_credentials_blocking(&self) -> Result<Credentials, Error> {
+        self.fetch_blocking()
+    }
+
+    fn invalidate(&self) {
+        self.invalidate_cache();
+    }
+}
+
+fn fetch_failed(request: &RedactedUri<'_>, error: &Error) -> Error {
+    let reason = error
+        .message()
+        .map(str::to_owned)
+        .unwrap_or_else(|| error.to_string())
+        .replace(request.raw, &request.redacted)
+        .replace(request.parsed.as_str(), &request.redacted);
+    Error::auth(format!(
+        "failed to fetch credentials from {}: {reason}",
+        request.redacted
+    ))
+}
+
+fn parse_credentials(response: Response, redacted: &str) -> Result<Credentials, Error> {
+    if !response.status.is_success() {
+        return Err(Error::auth(format!(
+            "failed to fetch credentials from {redacted} (http status {})",
+            response.status
+        )));
+    }
+
+    #[derive(serde::Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct UriCredentials {
+        #[serde(default)]
+        code: Option<String>,
+        #[serde(flatten)]
+        credentials: SessionCredentials,
+    }
+
+    let parsed: UriCredentials = serde_json::from_slice(&response.body)
+        .map_err(|e| Error::auth(format!("invalid credentials response from {redacted}: {e}")))?;
+    if let Some(code) = parsed.code.filter(|code| code != "Success") {
+        return Err(Error::auth(format!(
+            "credentials endpoint {redacted} returned code {code}"
+        )));
+    }
+    parsed.credentials.into_credentials(PROVIDER_NAME)
+}
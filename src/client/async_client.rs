@@ -1,31 +1,36 @@
-use std::{collections::BTreeMap, sync::Arc, time::Duration};
-
-#[cfg(feature = "tracing")]
-use std::time::Instant;
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use http::{HeaderMap, HeaderValue, Method, header};
 use serde::de::DeserializeOwned;
 
 use crate::{
     api::{BillingService, EcsService, StsService},
-    auth::Auth,
+    auth::{
+        AccessKey, Auth, CliProfileProvider, CredentialsCache, DefaultCredentialsChain, Env,
+        SharedCredentialsProvider, SignatureAlgorithm,
+    },
     error::{Error, ErrorInfo},
     transport::{
-        AsyncTransport, Request, Response,
+        AsyncTransport, Body, BoxError, Certificate, ConnectionConfig, Identity, Proxy,
+        ProxyConfig, Request, Response, collect, default_async_transport,
         retry::{RetryPolicy, backoff_delay, parse_retry_after, should_retry_status},
     },
-    util::{rpc, url as url_util},
+    types::{RegionId, roa::RoaRequest},
+    util::{roa, rpc, url as url_util},
 };
 
-#[cfg(feature = "rustls")]
-use crate::transport::async_transport::HyperRustlsTransport;
-#[cfg(feature = "native-tls")]
-use crate::transport::async_transport::ReqwestTransport;
-
+use super::clock::Clock;
 use super::common::{
-    AliyunEnvelope, classify_aliyun_error, classify_http_error, extract_request_id,
-    maybe_body_snippet,
+    AliyunEnvelope, ResponseHead, classify_aliyun_error, classify_http_error, extract_request_id,
+    is_expired_token_error, maybe_body_snippet, regional_endpoint,
 };
+use super::interceptor::{Interceptor, Interceptors, Operation, ResponseMetadata};
+use super::response::ResponseStream;
 
 #[derive(Clone)]
 pub struct Client {
@@ -34,9 +39,13 @@ pub struct Client {
 
 struct Inner {
     auth: Auth,
+    region: Option<RegionId>,
+    credentials: CredentialsCache,
     endpoints: Endpoints,
     defaults: RequestDefaults,
     retry: RetryPolicy,
+    clock: Arc<Clock>,
+    interceptors: Interceptors,
     transport: Arc<dyn AsyncTransport>,
 }
 
@@ -50,21 +59,24 @@ struct Endpoints {
 #[derive(Debug, Clone)]
 struct RequestDefaults {
     timeout: Duration,
-    connect_timeout: Duration,
     default_headers: HeaderMap,
     capture_body_snippet: bool,
     body_snippet_max_len: usize,
+    signature_algorithm: SignatureAlgorithm,
 }
 
 pub struct ClientBuilder {
     auth: Auth,
-    ecs_endpoint: String,
-    sts_endpoint: String,
+    region: Option<RegionId>,
+    profile_region: Result<Option<RegionId>, Error>,
+    ecs_endpoint: Option<String>,
+    sts_endpoint: Option<String>,
     billing_endpoint: String,
     defaults: RequestDefaults,
     retry: RetryPolicy,
-    #[cfg(test)]
-    transport_override: Option<Arc<dyn AsyncTransport>>,
+    connection: ConnectionConfig,
+    interceptors: Interceptors,
+    transport: Option<Arc<dyn AsyncTransport>>,
 }
 
 impl Client {
@@ -77,19 +89,22 @@ impl Client {
 
         ClientBuilder {
             auth: Auth::none(),
-            ecs_endpoint: "https://ecs.aliyuncs.com/".to_owned(),
-            sts_endpoint: "https://sts.aliyuncs.com/".to_owned(),
+            region: None,
+            profile_region: Ok(None),
+            ecs_endpoint: None,
+            sts_endpoint: None,
             billing_endpoint: "https://business.aliyuncs.com/".to_owned(),
             defaults: RequestDefaults {
                 timeout: Duration::from_secs(30),
-                connect_timeout: Duration::from_secs(10),
                 default_headers,
                 capture_body_snippet: true,
                 body_snippet_max_len: 4096,
+                signature_algorithm: SignatureAlgorithm::default(),
             },
             retry: RetryPolicy::default(),
-            #[cfg(test)]
-            transport_override: None,
+            connection: ConnectionConfig::default(),
+            interceptors: Interceptors::default(),
+            transport: None,
         }
     }
 
@@ -110,35 +125,283 @@ impl Client {
         base_url: &url::Url,
         action: &'static str,
         version: &'static str,
-        mut params: BTreeMap<String, String>,
+        params: BTreeMap<String, String>,
+        form_body: bool,
     ) -> Result<T, Error> {
-        params.insert("Action".to_owned(), action.to_owned());
-        params.insert("Version".to_owned(), version.to_owned());
-        params
-            .entry("Format".to_owned())
-            .or_insert("JSON".to_owned());
-
-        let Some(access_key) = self.inner.auth.as_access_key() else {
-            return Err(Error::invalid_config(
-                "access key authentication is required",
-                None,
-            ));
+        let mut operation = Operation::new(Some(action.to_owned()), version, params);
+        operation.set_form_body(form_body);
+        let result = async {
+            self.inner.interceptors.before_sign(&mut operation)?;
+            let access_key = self.inner.credentials.resolve(&self.inner.auth).await?;
+            match self.send_rpc(base_url, &operation, Some(&access_key)).await {
+                Err(error) if self.invalidate_expired_credentials(&error) => {
+                    let access_key = self.inner.credentials.resolve(&self.inner.auth).await?;
+                    self.send_rpc(base_url, &operation, Some(&access_key)).await
+                }
+                result => result,
+            }
+        }
+        .await;
+        result.map_err(|error| self.inner.interceptors.on_error(&operation, error))
+    }
+
+    /// Send an RPC call without signing it (e.g. STS `AssumeRoleWithOIDC`/`AssumeRoleWithSAML`).
+    pub(crate) async fn rpc_json_anonymous<T: DeserializeOwned>(
+        &self,
+        base_url: &url::Url,
+        action: &'static str,
+        version: &'static str,
+        params: BTreeMap<String, String>,
+        form_body: bool,
+    ) -> Result<T, Error> {
+        let mut operation = Operation::new(Some(action.to_owned()), version, params);
+        operation.set_form_body(form_body);
+        let result = async {
+            self.inner.interceptors.before_sign(&mut operation)?;
+            self.send_rpc(base_url, &operation, None).await
+        }
+        .await;
+        result.map_err(|error| self.inner.interceptors.on_error(&operation, error))
+    }
+
+    async fn send_rpc<T: DeserializeOwned>(
+        &self,
+        base_url: &url::Url,
+        operation: &Operation,
+        access_key: Option<&AccessKey>,
+    ) -> Result<T, Error> {
+        let sign = || {
+            rpc::request(
+                base_url,
+                operation.action().unwrap_or_default(),
+                operation.version(),
+                operation.params().clone(),
+                access_key,
+                self.inner.defaults.signature_algorithm,
+                self.inner.defaults.default_headers.clone(),
+                self.inner.clock.now(),
+                operation.form_body(),
+            )
+        };
+
+        let skew = self.inner.clock.skew_secs();
+        let call = sign()?;
+        match self
+            .send_json(
+                operation,
+                call.method,
+                call.url,
+                call.headers,
+                call.body.into(),
+            )
+            .await
+        {
+            Err(error) if self.inner.clock.should_retry(skew, &error) => {
+                let call = sign()?;
+                self.send_json(
+                    operation,
+                    call.method,
+                    call.url,
+                    call.headers,
+                    call.body.into(),
+                )
+                .await
+            }
+            result => result,
+        }
+    }
+
+    /// Call a ROA (RESTful) API and decode its JSON response.
+    ///
+    /// The request is signed with the client's credentials and
+    /// [`signature_algorithm`](ClientBuilder::signature_algorithm). An empty response body
+    /// decodes as `null`.
+    pub async fn roa_json<T: DeserializeOwned>(&self, mut request: RoaRequest) -> Result<T, Error> {
+        let mut operation = Operation::new(
+            request.action.clone(),
+            request.version.clone(),
+            std::mem::take(&mut request.query),
+        );
+        let result = async {
+            self.inner.interceptors.before_sign(&mut operation)?;
+            request.query = operation.params().clone();
+            let access_key = self.inner.credentials.resolve(&self.inner.auth).await?;
+            match self.send_roa(&operation, &request, &access_key).await {
+                Err(error)
+                    if self.invalidate_expired_credentials(&error)
+                        && request.body.is_replayable() =>
+                {
+                    let access_key = self.inner.credentials.resolve(&self.inner.auth).await?;
+                    self.send_roa(&operation, &request, &access_key).await
+                }
+                result => result,
+            }
+        }
+        .await;
+        result.map_err(|error| self.inner.interceptors.on_error(&operation, error))
+    }
+
+    async fn send_roa<T: DeserializeOwned>(
+        &self,
+        operation: &Operation,
+        request: &RoaRequest,
+        access_key: &AccessKey,
+    ) -> Result<T, Error> {
+        let sign = || {
+            roa::request(
+                request,
+                access_key,
+                self.inner.defaults.signature_algorithm,
+                self.inner.defaults.default_headers.clone(),
+                self.inner.clock.now(),
+            )
         };
 
-        rpc::inject_common_rpc_params(&mut params, access_key)?;
+        let skew = self.inner.clock.skew_secs();
+        let (url, headers) = sign()?;
+        match self
+            .send_json(
+                operation,
+                request.method.clone(),
+                url,
+                headers,
+                request.body.clone(),
+            )
+            .await
+        {
+            Err(error)
+                if request.body.is_replayable() && self.inner.clock.should_retry(skew, &error) =>
+            {
+                let (url, headers) = sign()?;
+                self.send_json(
+                    operation,
+                    request.method.clone(),
+                    url,
+                    headers,
+                    request.body.clone(),
+                )
+                .await
+            }
+            result => result,
+        }
+    }
 
-        let canonical_query = rpc::canonical_query(&params);
-        let signature = rpc::signature(
-            &Method::GET,
-            &canonical_query,
-            access_key.access_key_secret.expose(),
-        )?;
-        params.insert("Signature".to_owned(), signature);
+    /// Call a ROA (RESTful) API and stream its response body, e.g. an object download or
+    /// a bill export too large to buffer.
+    ///
+    /// Signing, retries and error classification are those of
+    /// [`roa_json`](Self::roa_json); the call returns once a successful response's headers
+    /// arrive, and the [timeout](ClientBuilder::timeout) does not cover reading the body.
+    pub async fn roa_stream(&self, mut request: RoaRequest) -> Result<ResponseStream, Error> {
+        let mut operation = Operation::new(
+            request.action.clone(),
+            request.version.clone(),
+            std::mem::take(&mut request.query),
+        );
+        let result = async {
+            self.inner.interceptors.before_sign(&mut operation)?;
+            request.query = operation.params().clone();
+            let access_key = self.inner.credentials.resolve(&self.inner.auth).await?;
+            match self
+                .send_roa_stream(&operation, &request, &access_key)
+                .await
+            {
+                Err(error)
+                    if self.invalidate_expired_credentials(&error)
+                        && request.body.is_replayable() =>
+                {
+                    let access_key = self.inner.credentials.resolve(&self.inner.auth).await?;
+                    self.send_roa_stream(&operation, &request, &access_key)
+                        .await
+                }
+                result => result,
+            }
+        }
+        .await;
+        result.map_err(|error| self.inner.interceptors.on_error(&operation, error))
+    }
 
-        let mut url = url_util::endpoint(base_url, &[])?;
-        url.set_query(Some(&rpc::canonical_query(&params)));
+    async fn send_roa_stream(
+        &self,
+        operation: &Operation,
+        request: &RoaRequest,
+        access_key: &AccessKey,
+    ) -> Result<ResponseStream, Error> {
+        let sign = || {
+            roa::request(
+                request,
+                access_key,
+                self.inner.defaults.signature_algorithm,
+                self.inner.defaults.default_headers.clone(),
+                self.inner.clock.now(),
+            )
+        };
+
+        let skew = self.inner.clock.skew_secs();
+        let (url, headers) = sign()?;
+        match self
+            .send_stream(
+                operation,
+                request.method.clone(),
+                url,
+                headers,
+                request.body.clone(),
+            )
+            .await
+        {
+            Err(error)
+                if request.body.is_replayable() && self.inner.clock.should_retry(skew, &error) =>
+            {
+                let (url, headers) = sign()?;
+                self.send_stream(
+                    operation,
+                    request.method.clone(),
+                    url,
+                    headers,
+                    request.body.clone(),
+                )
+                .await
+            }
+            result => result,
+        }
+    }
 
-        self.send_json(Method::GET, url).await
+    /// Seconds the server clock is ahead of the local clock (negative when behind), as
+    /// learned from response `Date` headers; `0` until a skew is detected.
+    ///
+    /// Requests are signed with the corrected time, and a request rejected for its
+    /// timestamp is signed and sent once more after the skew is detected.
+    pub fn clock_skew(&self) -> i64 {
+        self.inner.clock.skew_secs()
+    }
+
+    /// Drop cached credentials rejected with an expired security token; returns whether
+    /// the request should be signed with new credentials and sent again.
+    fn invalidate_expired_credentials(&self, error: &Error) -> bool {
+        is_expired_token_error(error) && self.inner.credentials.invalidate(&self.inner.auth)
+    }
+
+    /// Region configured on the builder or taken from the CLI profile, if any.
+    pub fn region(&self) -> Option<&RegionId> {
+        self.inner.region.as_ref()
+    }
+
+    /// A client that signs with `auth` instead, sharing this client's endpoints, settings,
+    /// clock skew correction and transport (e.g. one client per account of a role chain).
+    pub fn with_auth(&self, auth: Auth) -> Client {
+        Client {
+            inner: Arc::new(Inner {
+                auth,
+                region: self.inner.region.clone(),
+                credentials: CredentialsCache::new(),
+                endpoints: self.inner.endpoints.clone(),
+                defaults: self.inner.defaults.clone(),
+                retry: self.inner.retry.clone(),
+                clock: self.inner.clock.clone(),
+                interceptors: self.inner.interceptors.clone(),
+                transport: self.inner.transport.clone(),
+            }),
+        }
     }
 
     pub(crate) fn endpoint_ecs(&self) -> &url::Url {
@@ -155,56 +418,33 @@ impl Client {
 
     async fn send_json<T: DeserializeOwned>(
         &self,
+        operation: &Operation,
         method: Method,
         url: url::Url,
+        headers: HeaderMap,
+        body: Body,
     ) -> Result<T, Error> {
         let path = url.path().to_owned();
         #[cfg(feature = "tracing")]
         let start = Instant::now();
 
         #[cfg(feature = "tracing")]
-        let host = url.host_str().unwrap_or("<unknown>");
-        #[cfg(feature = "tracing")]
-        let span = tracing::info_span!(
-            "alibabacloud.request",
-            method = %method,
-            host = host,
-            path = %path,
-            status = tracing::field::Empty,
-            latency_ms = tracing::field::Empty,
-            retry_count = tracing::field::Empty,
-            request_id = tracing::field::Empty,
-        );
+        let span = request_span(&method, &url);
         #[cfg(feature = "tracing")]
         let _guard = span.enter();
 
-        let headers = self.inner.defaults.default_headers.clone();
-
         let request = Request {
             method: method.clone(),
             url,
             headers,
+            body,
             timeout: self.inner.defaults.timeout,
         };
-
-        let response = match self.send_with_retries(&request).await {
-            Ok(response) => response,
-            Err(error) => {
-                #[cfg(feature = "tracing")]
-                {
-                    let latency_ms = start.elapsed().as_millis() as u64;
-                    record_span_outcome(error.status(), error.request_id(), latency_ms);
-                    tracing::warn!(error_kind = error_kind(&error), "request failed");
-                }
-                return Err(error);
-            }
-        };
-
-        let request_id = extract_request_id(&response.headers);
-        #[cfg(feature = "tracing")]
-        {
-            record_span_outcome(Some(response.status), request_id.as_deref(), 0);
-        }
+        let (response, request_id) = self
+            .exchange(operation, &request, |request| {
+                self.inner.transport.send(request)
+            })
+            .await?;
 
         if !response.status.is_success() {
             let error = classify_http_error(
@@ -224,7 +464,12 @@ impl Client {
             return Err(error);
         }
 
-        let mut deserializer = serde_json::Deserializer::from_slice(&response.body);
+        let body: &[u8] = if response.body.is_empty() {
+            b"null"
+        } else {
+            &response.body
+        };
+        let mut deserializer = serde_json::Deserializer::from_slice(body);
         let parsed = serde_path_to_error::deserialize::<_, AliyunEnvelope<T>>(&mut deserializer);
         match parsed {
             Ok(AliyunEnvelope::Ok(value)) => {
@@ -263,6 +508,7 @@ impl Client {
                         status: Some(response.status),
                         method: Some(method),
                         path: Some(path),
+                        code: None,
                         request_id,
                         body_snippet: maybe_body_snippet(
                             self.inner.defaults.capture_body_snippet,
@@ -284,27 +530,158 @@ impl Client {
         }
     }
 
-    async fn send_with_retries(&self, request: &Request) -> Result<Response, Error> {
+    async fn send_stream(
+        &self,
+        operation: &Operation,
+        method: Method,
+        url: url::Url,
+        headers: HeaderMap,
+        body: Body,
+    ) -> Result<ResponseStream, Error> {
+        let path = url.path().to_owned();
+        #[cfg(feature = "tracing")]
+        let start = Instant::now();
+        #[cfg(feature = "tracing")]
+        let span = request_span(&method, &url);
+        #[cfg(feature = "tracing")]
+        let _guard = span.enter();
+
+        let request = Request {
+            method: method.clone(),
+            url,
+            headers,
+            body,
+            timeout: self.inner.defaults.timeout,
+        };
+        let (response, request_id) = self
+            .exchange(operation, &request, |request| {
+                self.inner.transport.send_streaming(request)
+            })
+            .await?;
+
+        if !response.status.is_success() {
+            // Error bodies are small; read them to classify the error as `send_json` does.
+            let error = match collect(response.body).await {
+                Ok(body) => classify_http_error(
+                    method,
+                    path,
+                    Response {
+                        status: response.status,
+                        headers: response.headers,
+                        body,
+                    },
+                    request_id,
+                    self.inner.defaults.capture_body_snippet,
+                    self.inner.defaults.body_snippet_max_len,
+                ),
+                Err(source) => Error::Transport {
+                    info: Box::new(ErrorInfo {
+                        status: Some(response.status),
+                        method: Some(method),
+                        path: Some(path),
+                        request_id,
+                        ..ErrorInfo::default()
+                    }),
+                    source,
+                },
+            };
+            #[cfg(feature = "tracing")]
+            {
+                let latency_ms = start.elapsed().as_millis() as u64;
+                record_span_outcome(error.status(), error.request_id(), latency_ms);
+                tracing::warn!(error_kind = error_kind(&error), "request failed");
+            }
+            return Err(error);
+        }
+
+        #[cfg(feature = "tracing")]
+        {
+            let latency_ms = start.elapsed().as_millis() as u64;
+            record_span_outcome(Some(response.status), request_id.as_deref(), latency_ms);
+        }
+        Ok(ResponseStream::new(response, request_id, method, path))
+    }
+
+    /// Check the signed request with the interceptors, send it with retries and report the
+    /// response to them; returns the response, whatever its status, with its request ID.
+    async fn exchange<R, F>(
+        &self,
+        operation: &Operation,
+        request: &Request,
+        send: impl Fn(Request) -> F,
+    ) -> Result<(R, Option<String>), Error>
+    where
+        R: ResponseHead,
+        F: Future<Output = Result<R, BoxError>>,
+    {
+        #[cfg(feature = "tracing")]
+        let start = Instant::now();
+        self.inner.interceptors.after_sign(operation, request)?;
+
+        let sent = Instant::now();
+        let response = match self.send_with_retries(request, send).await {
+            Ok(response) => {
+                self.inner.clock.observe(response.headers());
+                response
+            }
+            Err(error) => {
+                #[cfg(feature = "tracing")]
+                {
+                    let latency_ms = start.elapsed().as_millis() as u64;
+                    record_span_outcome(error.status(), error.request_id(), latency_ms);
+                    tracing::warn!(error_kind = error_kind(&error), "request failed");
+                }
+                return Err(error);
+            }
+        };
+
+        let request_id = extract_request_id(response.headers());
+        self.inner.interceptors.after_response(
+            operation,
+            &ResponseMetadata::new(
+                response.status(),
+                response.headers(),
+                request_id.as_deref(),
+                sent.elapsed(),
+            ),
+        );
+        #[cfg(feature = "tracing")]
+        {
+            record_span_outcome(Some(response.status()), request_id.as_deref(), 0);
+        }
+        Ok((response, request_id))
+    }
+
+    async fn send_with_retries<R, F>(
+        &self,
+        request: &Request,
+        send: impl Fn(Request) -> F,
+    ) -> Result<R, Error>
+    where
+        R: ResponseHead,
+        F: Future<Output = Result<R, BoxError>>,
+    {
         let mut attempt = 0usize;
         loop {
-            let result = self.inner.transport.send(request.clone()).await;
+            let result = send(request.clone()).await;
             match result {
                 Ok(response) => {
                     if attempt >= self.inner.retry.max_retries
-                        || !should_retry_status(response.status)
+                        || !should_retry_status(response.status())
+                        || !request.body.is_replayable()
                     {
                         #[cfg(feature = "tracing")]
                         tracing::Span::current().record("retry_count", attempt as u64);
                         return Ok(response);
                     }
 
-                    let delay = parse_retry_after(&response.headers)
+                    let delay = parse_retry_after(response.headers())
                         .unwrap_or_else(|| backoff_delay(&self.inner.retry, attempt));
                     #[cfg(feature = "tracing")]
                     tracing::debug!(
                         retry_count = attempt + 1,
                         delay_ms = delay.as_millis() as u64,
-                        status = response.status.as_u16(),
+                        status = response.status().as_u16(),
                         "retrying request"
                     );
                     tokio::time::sleep(delay).await;
@@ -313,6 +690,7 @@ impl Client {
                 }
                 Err(source) => {
                     if attempt < self.inner.retry.max_retries
+                        && request.body.is_replayable()
                         && is_retryable_transport_error(&*source)
                     {
                         let delay = backoff_delay(&self.inner.retry, attempt);
@@ -334,6 +712,7 @@ impl Client {
                             status: None,
                             method: Some(request.method.clone()),
                             path: Some(request.url.path().to_owned()),
+                            code: None,
                             message: None,
                             request_id: None,
                             body_snippet: None,
@@ -346,6 +725,20 @@ impl Client {
     }
 }
 
+#[cfg(feature = "tracing")]
+fn request_span(method: &Method, url: &url::Url) -> tracing::Span {
+    tracing::info_span!(
+        "alibabacloud.request",
+        method = %method,
+        host = url.host_str().unwrap_or("<unknown>"),
+        path = %url.path(),
+        status = tracing::field::Empty,
+        latency_ms = tracing::field::Empty,
+        retry_count = tracing::field::Empty,
+        request_id = tracing::field::Empty,
+    )
+}
+
 #[cfg(feature = "tracing")]
 fn record_span_outcome(
     status: Option<http::StatusCode>,
@@ -379,18 +772,54 @@ fn error_kind(error: &Error) -> &'static str {
 }
 
 impl ClientBuilder {
+    /// Start a builder whose credentials come from [`Auth::default_chain`].
+    ///
+    /// The region is taken from the selected `aliyun` CLI profile when it sets one; if the
+    /// CLI config file exists but cannot be read, [`build`](Self::build) returns the error.
+    pub fn from_env() -> Self {
+        Self::from_env_with(Env::real())
+    }
+
+    fn from_env_with(env: Env) -> Self {
+        let chain = DefaultCredentialsChain::with_env(env);
+        let profile_region = chain.profile_region();
+        let mut builder = Client::builder().auth(Auth::Provider(
+            SharedCredentialsProvider::from_builtin(chain),
+        ));
+        builder.profile_region = profile_region;
+        builder
+    }
+
+    /// Use the credentials and `region_id` of an `aliyun` CLI profile.
+    ///
+    /// The config file is read once, here; [`build`](Self::build) returns any error reading
+    /// it unless an explicit [`region`](Self::region), which takes precedence over the
+    /// profile's region, is set.
+    pub fn profile(mut self, name: impl Into<String>) -> Self {
+        let provider = CliProfileProvider::new().profile(name);
+        self.profile_region = provider.region_id();
+        self.auth = Auth::Provider(SharedCredentialsProvider::from_builtin(provider));
+        self
+    }
+
     pub fn auth(mut self, auth: Auth) -> Self {
         self.auth = auth;
         self
     }
 
+    /// Region used for the default regional endpoints (`ecs.{region}.aliyuncs.com`, ...).
+    pub fn region(mut self, region: impl Into<RegionId>) -> Self {
+        self.region = Some(region.into());
+        self
+    }
+
     pub fn ecs_endpoint(mut self, endpoint: impl AsRef<str>) -> Self {
-        self.ecs_endpoint = endpoint.as_ref().to_owned();
+        self.ecs_endpoint = Some(endpoint.as_ref().to_owned());
         self
     }
 
     pub fn sts_endpoint(mut self, endpoint: impl AsRef<str>) -> Self {
-        self.sts_endpoint = endpoint.as_ref().to_owned();
+        self.sts_endpoint = Some(endpoint.as_ref().to_owned());
         self
     }
 
@@ -405,7 +834,75 @@ impl ClientBuilder {
     }
 
     pub fn connect_timeout(mut self, timeout: Duration) -> Self {
-        self.defaults.connect_timeout = timeout;
+        self.connection.connect_timeout = timeout;
+        self
+    }
+
+    /// Maximum number of idle connections kept per host (unlimited by default).
+    pub fn pool_max_idle_per_host(mut self, max_idle: usize) -> Self {
+        self.connection.pool_max_idle_per_host = Some(max_idle);
+        self
+    }
+
+    /// How long an idle connection is kept for reuse (default 90 seconds).
+    pub fn pool_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.connection.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Send TCP keepalive probes on idle connections after `interval` (off by default).
+    pub fn tcp_keepalive(mut self, interval: Duration) -> Self {
+        self.connection.tcp_keepalive = Some(interval);
+        self
+    }
+
+    /// Offer HTTP/2 during the TLS handshake and multiplex requests over one connection
+    /// per host when the endpoint accepts it (HTTP/1.1 only by default).
+    ///
+    /// Plain `http://` endpoints keep using HTTP/1.1.
+    pub fn http2(mut self, enabled: bool) -> Self {
+        self.connection.http2 = enabled;
+        self
+    }
+
+    /// Connect through `proxy` (see [`Proxy`] for the supported schemes and `NO_PROXY`
+    /// rules).
+    ///
+    /// By default the proxy is taken from `HTTPS_PROXY`/`ALL_PROXY` and `NO_PROXY` when
+    /// the client is built ([`Proxy::from_env`]); an invalid proxy URL there fails
+    /// [`build`](Self::build).
+    pub fn proxy(mut self, proxy: Proxy) -> Self {
+        self.connection.proxy = ProxyConfig::Custom(proxy);
+        self
+    }
+
+    /// Connect directly, ignoring the proxy environment variables.
+    pub fn no_proxy(mut self) -> Self {
+        self.connection.proxy = ProxyConfig::Direct;
+        self
+    }
+
+    /// Trust `cert` as a root certificate, such as a private CA's, in addition to the
+    /// built-in roots.
+    pub fn add_root_certificate(mut self, cert: Certificate) -> Self {
+        self.connection.tls.root_certs.push(cert);
+        self
+    }
+
+    /// Whether to trust the built-in root certificates (the platform's; Mozilla's for
+    /// the blocking client) as well as those added with
+    /// [`add_root_certificate`](Self::add_root_certificate) (default `true`).
+    ///
+    /// Disable them to only trust the added roots.
+    pub fn tls_built_in_root_certs(mut self, enabled: bool) -> Self {
+        self.connection.tls.built_in_roots = enabled;
+        self
+    }
+
+    /// Present `identity` as the client certificate when the server asks for one
+    /// (mutual TLS).
+    pub fn identity(mut self, identity: Identity) -> Self {
+        self.connection.tls.identity = Some(identity);
         self
     }
 
@@ -419,6 +916,12 @@ impl ClientBuilder {
         self
     }
 
+    /// How requests are signed (default [`SignatureAlgorithm::HmacSha1`]).
+    pub fn signature_algorithm(mut self, algorithm: SignatureAlgorithm) -> Self {
+        self.defaults.signature_algorithm = algorithm;
+        self
+    }
+
     pub fn max_retries(mut self, max_retries: usize) -> Self {
         self.retry.max_retries = max_retries;
         self
@@ -444,69 +947,62 @@ impl ClientBuilder {
         self
     }
 
-    #[cfg(test)]
-    pub(crate) fn transport_override(mut self, transport: Arc<dyn AsyncTransport>) -> Self {
-        self.transport_override = Some(transport);
+    /// Send requests through a custom HTTP stack (an instrumented client, a shared
+    /// connection pool or a test double) instead of the built-in transport.
+    ///
+    /// The transport is responsible for [`Request::timeout`](crate::transport::Request::timeout);
+    /// [`connect_timeout`](Self::connect_timeout), the `pool_*` settings,
+    /// [`tcp_keepalive`](Self::tcp_keepalive), [`http2`](Self::http2), the proxy and the
+    /// TLS settings only configure the built-in transport.
+    pub fn transport(mut self, transport: Arc<dyn AsyncTransport>) -> Self {
+        self.transport = Some(transport);
+        self
+    }
+
+    /// Add an interceptor; interceptors run in the order they are added.
+    pub fn interceptor(mut self, interceptor: Arc<dyn Interceptor>) -> Self {
+        self.interceptors.push(interceptor);
         self
     }
 
     pub fn build(self) -> Result<Client, Error> {
-        let ecs = url_util::parse_base_url(&self.ecs_endpoint)?;
-        let sts = url_util::parse_base_url(&self.sts_endpoint)?;
+        let region = match self.region {
+            Some(region) => Some(region),
+            None => self.profile_region?,
+        };
+        let ecs = url_util::parse_base_url(
+            &self
+                .ecs_endpoint
+                .unwrap_or_else(|| regional_endpoint("ecs", region.as_ref())),
+        )?;
+        let sts = url_util::parse_base_url(
+            &self
+                .sts_endpoint
+                .unwrap_or_else(|| regional_endpoint("sts", region.as_ref())),
+        )?;
         let billing = url_util::parse_base_url(&self.billing_endpoint)?;
 
-        let transport: Arc<dyn AsyncTransport> = {
-            #[cfg(test)]
-            if let Some(transport) = self.transport_override {
-                transport
-            } else {
-                default_transport(self.defaults.connect_timeout)?
-            }
-            #[cfg(not(test))]
-            {
-                default_transport(self.defaults.connect_timeout)?
-            }
+        let transport = match self.transport {
+            Some(transport) => transport,
+            None => default_async_transport(&self.connection)?,
         };
 
         Ok(Client {
             inner: Arc::new(Inner {
                 auth: self.auth,
+                region,
+                credentials: CredentialsCache::new(),
                 endpoints: Endpoints { ecs, sts, billing },
                 defaults: self.defaults,
                 retry: self.retry,
+                clock: Arc::default(),
+                interceptors: self.interceptors,
                 transport,
             }),
         })
     }
 }
 
-fn default_transport(connect_timeout: Duration) -> Result<Arc<dyn AsyncTransport>, Error> {
-    #[cfg(feature = "native-tls")]
-    {
-        return Ok(Arc::new(ReqwestTransport::new(connect_timeout).map_err(
-            |e| Error::invalid_config("failed to build async http transport (reqwest)", Some(e)),
-        )?));
-    }
-
-    #[cfg(feature = "rustls")]
-    {
-        return Ok(Arc::new(
-            HyperRustlsTransport::new(connect_timeout).map_err(|e| {
-                Error::invalid_config(
-                    "failed to build async http transport (hyper-rustls)",
-                    Some(e),
-                )
-            })?,
-        ));
-    }
-
-    #[allow(unreachable_code)]
-    Err(Error::invalid_config(
-        "no async http transport available",
-        None,
-    ))
-}
-
 fn is_retryable_transport_error(error: &(dyn std::error::Error + 'static)) -> bool {
     if let Some(err) = error.downcast_ref::<reqwest::Error>() {
         return err.is_timeout() || err.is_connect();
@@ -560,8 +1056,8 @@ mod tests {
     use http::{HeaderMap, StatusCode};
 
     use crate::{
-        auth::Auth,
-        transport::{AsyncTransport, BoxError, Request, Response},
+        auth::{Auth, Credentials, CredentialsFuture, CredentialsProvider},
+        transport::{AsyncTransport, BoxError, Request, Response, TransportFuture},
     };
 
     use super::*;
@@ -591,12 +1087,7 @@ mod tests {
     }
 
     impl AsyncTransport for MockAsyncTransport {
-        fn send<'a>(
-            &'a self,
-            request: Request,
-        ) -> std::pin::Pin<
-            Box<dyn std::future::Future<Output = Result<Response, BoxError>> + Send + 'a>,
-        > {
+        fn send(&self, request: Request) -> TransportFuture<'_> {
             self.calls.fetch_add(1, Ordering::SeqCst);
             *self.last_request.lock().unwrap() = Some(request.clone());
 
@@ -616,6 +1107,39 @@ mod tests {
         }
     }
 
+    #[derive(Debug)]
+    struct CountingProvider {
+        calls: AtomicUsize,
+        invalidations: AtomicUsize,
+        ttl: Duration,
+    }
+
+    impl CountingProvider {
+        fn new(ttl: Duration) -> Self {
+            Self {
+                calls: AtomicUsize::new(0),
+                invalidations: AtomicUsize::new(0),
+                ttl,
+            }
+        }
+    }
+
+    impl CredentialsProvider for Arc<CountingProvider> {
+        fn provide_credentials(&self) -> CredentialsFuture<'_> {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+            let expiration = std::time::SystemTime::now() + self.ttl;
+            Box::pin(async move {
+                Ok(Credentials::new(format!("id-{call}"), "secret")
+                    .with_security_token("token")
+                    .with_expiration(expiration))
+            })
+        }
+
+        fn invalidate(&self) {
+            self.invalidations.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
     fn response(status: StatusCode, headers: HeaderMap, body: &str) -> Response {
         Response {
             status,
@@ -624,6 +1148,20 @@ mod tests {
         }
     }
 
+    const IDENTITY: &str = r#"{"IdentityType":"Account","RequestId":"req","AccountId":"1","PrincipalId":"p","UserId":"u","Arn":"arn","RoleId":null}"#;
+
+    /// A home directory whose `aliyun` CLI config selects a profile in `cn-shanghai`.
+    fn cli_home() -> std::path::PathBuf {
+        let home = std::env::temp_dir().join(format!("alibabacloud-home-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(home.join(".aliyun")).unwrap();
+        std::fs::write(
+            home.join(".aliyun").join("config.json"),
+            r#"{"current":"dev","profiles":[{"name":"dev","mode":"AK","access_key_id":"cli-id","access_key_secret":"cli-secret","region_id":"cn-shanghai"}]}"#,
+        )
+        .unwrap();
+        home
+    }
+
     #[tokio::test]
     async fn sts_error_body_is_classified_and_redacted() {
         let mut headers = HeaderMap::new();
@@ -638,7 +1176,7 @@ mod tests {
         let client = Client::builder()
             .auth(Auth::access_key("id", "secret"))
             .sts_endpoint("https://sts.example.com/")
-            .transport_override(transport)
+            .transport(transport)
             .build()
             .unwrap();
 
@@ -671,7 +1209,7 @@ mod tests {
             .max_retries(1)
             .retry_base_delay(Duration::from_millis(0))
             .retry_max_delay(Duration::from_millis(0))
-            .transport_override(transport.clone())
+            .transport(transport.clone())
             .build()
             .unwrap();
 
@@ -691,7 +1229,7 @@ mod tests {
         let client = Client::builder()
             .auth(Auth::access_key("id", "secret"))
             .capture_body_snippet(false)
-            .transport_override(transport)
+            .transport(transport)
             .build()
             .unwrap();
 
@@ -710,7 +1248,7 @@ mod tests {
         let client = Client::builder()
             .auth(Auth::access_key("id", "secret"))
             .ecs_endpoint("https://ecs.example.com/")
-            .transport_override(transport.clone())
+            .transport(transport.clone())
             .build()
             .unwrap();
 
@@ -727,4 +1265,444 @@ mod tests {
         assert!(query.contains("SignatureNonce="));
         assert!(query.contains("Signature="));
     }
+
+    #[tokio::test]
+    async fn signature_v3_moves_signing_into_headers() {
+        let transport = Arc::new(MockAsyncTransport::new(vec![response(
+            StatusCode::OK,
+            HeaderMap::new(),
+            "{}",
+        )]));
+
+        let client = Client::builder()
+            .auth(Auth::access_key_with_security_token(
+                "id", "secret", "token",
+            ))
+            .ecs_endpoint("https://ecs.example.com/")
+            .signature_algorithm(SignatureAlgorithm::Acs3HmacSha256)
+            .transport(transport.clone())
+            .build()
+            .unwrap();
+
+        let _ = client
+            .ecs()
+            .describe_regions(Default::default())
+            .await
+            .unwrap();
+
+        let request = transport.last_request().unwrap();
+        let query = request.url.query().unwrap_or_default();
+        assert!(!query.contains("Signature"));
+        assert!(!query.contains("Action="));
+        assert_eq!(request.headers["x-acs-action"], "DescribeRegions");
+        assert_eq!(request.headers["x-acs-version"], "2014-05-26");
+        assert_eq!(request.headers["host"], "ecs.example.com");
+        assert_eq!(request.headers["x-acs-security-token"], "token");
+        let authorization = request.headers[header::AUTHORIZATION].to_str().unwrap();
+        assert!(authorization.starts_with("ACS3-HMAC-SHA256 Credential=id,SignedHeaders=host;"));
+    }
+
+    #[tokio::test]
+    async fn clock_skew_is_learned_and_request_resigned_once() {
+        let server_time = std::time::SystemTime::now() + Duration::from_secs(3600);
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::DATE,
+            httpdate::fmt_http_date(server_time).parse().unwrap(),
+        );
+        let transport = Arc::new(MockAsyncTransport::new(vec![
+            response(
+                StatusCode::BAD_REQUEST,
+                headers,
+                r#"{"Code":"InvalidTimeStamp.Expired","Message":"Specified time stamp or date value is expired.","RequestId":"req"}"#,
+            ),
+            response(StatusCode::OK, HeaderMap::new(), "{}"),
+        ]));
+
+        let client = Client::builder()
+            .auth(Auth::access_key("id", "secret"))
+            .ecs_endpoint("https://ecs.example.com/")
+            .transport(transport.clone())
+            .build()
+            .unwrap();
+        assert_eq!(client.clock_skew(), 0);
+
+        client
+            .ecs()
+            .describe_regions(Default::default())
+            .await
+            .unwrap();
+
+        assert_eq!(transport.calls(), 2);
+        assert!((client.clock_skew() - 3600).abs() <= 2);
+        let other = client.with_auth(Auth::access_key("other", "secret"));
+        assert_eq!(other.clock_skew(), client.clock_skew());
+        let request = transport.last_request().unwrap();
+        let timestamp = request
+            .url
+            .query_pairs()
+            .find(|(key, _)| key == "Timestamp")
+            .map(|(_, value)| value.into_owned())
+            .unwrap();
+        let signed_at =
+            time::OffsetDateTime::parse(&timestamp, &time::format_description::well_known::Rfc3339)
+                .unwrap();
+        let drift = signed_at - time::OffsetDateTime::from(server_time);
+        assert!(drift.whole_seconds().abs() <= 2);
+    }
+
+    #[tokio::test]
+    async fn clock_skew_error_without_date_is_not_retried() {
+        let transport = Arc::new(MockAsyncTransport::new(vec![response(
+            StatusCode::BAD_REQUEST,
+            HeaderMap::new(),
+            r#"{"Code":"InvalidTimeStamp.Expired","Message":"expired","RequestId":"req"}"#,
+        )]));
+
+        let client = Client::builder()
+            .auth(Auth::access_key("id", "secret"))
+            .transport(transport.clone())
+            .build()
+            .unwrap();
+
+        let err = client.sts().get_caller_identity().await.unwrap_err();
+        assert_eq!(err.code(), Some("InvalidTimeStamp.Expired"));
+        assert_eq!(transport.calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn expired_security_token_refreshes_credentials_and_retries_once() {
+        let expired = r#"{"Code":"InvalidSecurityToken.Expired","Message":"Specified SecurityToken is expired.","RequestId":"req"}"#;
+        let transport = Arc::new(MockAsyncTransport::new(vec![
+            response(StatusCode::BAD_REQUEST, HeaderMap::new(), expired),
+            response(StatusCode::OK, HeaderMap::new(), "{}"),
+            response(StatusCode::BAD_REQUEST, HeaderMap::new(), expired),
+            response(StatusCode::BAD_REQUEST, HeaderMap::new(), expired),
+        ]));
+        let provider = Arc::new(CountingProvider::new(Duration::from_secs(3600)));
+
+        let client = Client::builder()
+            .auth(Auth::provider(provider.clone()))
+            .transport(transport.clone())
+            .build()
+            .unwrap();
+
+        client
+            .ecs()
+            .describe_regions(Default::default())
+            .await
+            .unwrap();
+        assert_eq!(transport.calls(), 2);
+        assert_eq!(provider.calls.load(Ordering::SeqCst), 2);
+        assert_eq!(provider.invalidations.load(Ordering::SeqCst), 1);
+        let query = transport
+            .last_request()
+            .unwrap()
+            .url
+            .query()
+            .unwrap()
+            .to_owned();
+        assert!(query.contains("AccessKeyId=id-2"));
+
+        let err = client
+            .ecs()
+            .describe_regions(Default::default())
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Some("InvalidSecurityToken.Expired"));
+        assert_eq!(transport.calls(), 4);
+    }
+
+    #[tokio::test]
+    async fn expired_security_token_with_static_credentials_is_not_retried() {
+        let transport = Arc::new(MockAsyncTransport::new(vec![response(
+            StatusCode::BAD_REQUEST,
+            HeaderMap::new(),
+            r#"{"Code":"InvalidSecurityToken.Expired","Message":"expired","RequestId":"req"}"#,
+        )]));
+
+        let client = Client::builder()
+            .auth(Auth::access_key_with_security_token(
+                "id", "secret", "token",
+            ))
+            .transport(transport.clone())
+            .build()
+            .unwrap();
+
+        let err = client.sts().get_caller_identity().await.unwrap_err();
+        assert_eq!(err.code(), Some("InvalidSecurityToken.Expired"));
+        assert_eq!(transport.calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn provider_credentials_are_cached_until_expiry() {
+        let transport = Arc::new(MockAsyncTransport::new(vec![
+            response(StatusCode::OK, HeaderMap::new(), "{}"),
+            response(StatusCode::OK, HeaderMap::new(), "{}"),
+        ]));
+        let provider = Arc::new(CountingProvider::new(Duration::from_secs(3600)));
+
+        let client = Client::builder()
+            .auth(Auth::provider(provider.clone()))
+            .transport(transport.clone())
+            .build()
+            .unwrap();
+
+        for _ in 0..2 {
+            client
+                .ecs()
+                .describe_regions(Default::default())
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(provider.calls.load(Ordering::SeqCst), 1);
+        let query = transport
+            .last_request()
+            .unwrap()
+            .url
+            .query()
+            .unwrap()
+            .to_owned();
+        assert!(query.contains("AccessKeyId=id-1"));
+        assert!(query.contains("SecurityToken=token"));
+    }
+
+    #[tokio::test]
+    async fn expiring_provider_credentials_are_refreshed_before_signing() {
+        let transport = Arc::new(MockAsyncTransport::new(vec![
+            response(StatusCode::OK, HeaderMap::new(), "{}"),
+            response(StatusCode::OK, HeaderMap::new(), "{}"),
+        ]));
+        let provider = Arc::new(CountingProvider::new(Duration::from_secs(60)));
+
+        let client = Client::builder()
+            .auth(Auth::provider(provider.clone()))
+            .transport(transport.clone())
+            .build()
+            .unwrap();
+
+        for _ in 0..2 {
+            client
+                .ecs()
+                .describe_regions(Default::default())
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(provider.calls.load(Ordering::SeqCst), 2);
+        let query = transport
+            .last_request()
+            .unwrap()
+            .url
+            .query()
+            .unwrap()
+            .to_owned();
+        assert!(query.contains("AccessKeyId=id-2"));
+    }
+
+    struct RecordingInterceptor {
+        name: &'static str,
+        events: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Interceptor for RecordingInterceptor {
+        fn before_sign(&self, operation: &mut Operation) -> Result<(), Error> {
+            let previous = operation
+                .params_mut()
+                .insert("Tag".to_owned(), self.name.to_owned());
+            self.events.lock().unwrap().push(format!(
+                "{} before_sign {} {} {previous:?}",
+                self.name,
+                operation.action().unwrap_or_default(),
+                operation.version(),
+            ));
+            Ok(())
+        }
+
+        fn after_sign(&self, _: &Operation, request: &Request) -> Result<(), Error> {
+            let signed = request
+                .url
+                .query()
+                .unwrap_or_default()
+                .contains("Signature=");
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("{} after_sign {signed}", self.name));
+            Ok(())
+        }
+
+        fn after_response(&self, _: &Operation, response: &ResponseMetadata<'_>) {
+            self.events.lock().unwrap().push(format!(
+                "{} after_response {} {:?}",
+                self.name,
+                response.status().as_u16(),
+                response.request_id(),
+            ));
+        }
+
+        fn on_error(&self, operation: &Operation, error: Error) -> Error {
+            Error::auth(format!(
+                "{} {}: {}",
+                self.name,
+                operation.params()["Tag"],
+                error.code().or(error.message()).unwrap_or_default()
+            ))
+        }
+    }
+
+    #[tokio::test]
+    async fn interceptors_run_in_order_around_each_call() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-acs-request-id", "req".parse().unwrap());
+        let transport = Arc::new(MockAsyncTransport::new(vec![
+            response(
+                StatusCode::OK,
+                headers,
+                r#"{"IdentityType":"Account","RequestId":"req","AccountId":"1","PrincipalId":"p","UserId":"u","Arn":"arn","RoleId":null}"#,
+            ),
+            response(
+                StatusCode::BAD_REQUEST,
+                HeaderMap::new(),
+                r#"{"Code":"Forbidden","Message":"denied","RequestId":"req"}"#,
+            ),
+        ]));
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let client = Client::builder()
+            .auth(Auth::access_key("id", "secret"))
+            .sts_endpoint("https://sts.example.com/")
+            .transport(transport.clone())
+            .interceptor(Arc::new(RecordingInterceptor {
+                name: "a",
+                events: events.clone(),
+            }))
+            .interceptor(Arc::new(RecordingInterceptor {
+                name: "b",
+                events: events.clone(),
+            }))
+            .build()
+            .unwrap();
+
+        client.sts().get_caller_identity().await.unwrap();
+        let query = transport
+            .last_request()
+            .unwrap()
+            .url
+            .query()
+            .unwrap()
+            .to_owned();
+        assert!(query.contains("Tag=b"));
+        assert_eq!(
+            *events.lock().unwrap(),
+            [
+                "a before_sign GetCallerIdentity 2015-04-01 None",
+                r#"b before_sign GetCallerIdentity 2015-04-01 Some("a")"#,
+                "a after_sign true",
+                "b after_sign true",
+                r#"a after_response 200 Some("req")"#,
+                r#"b after_response 200 Some("req")"#,
+            ]
+        );
+
+        let err = client.sts().get_caller_identity().await.unwrap_err();
+        assert_eq!(err.message(), Some("b b: a b: Forbidden"));
+    }
+
+    #[test]
+    fn region_selects_regional_endpoints_unless_overridden() {
+        let client = Client::builder()
+            .region("cn-beijing")
+            .sts_endpoint("http://127.0.0.1:1/")
+            .build()
+            .unwrap();
+        assert_eq!(client.region().map(|r| r.as_str()), Some("cn-beijing"));
+        assert_eq!(
+            client.endpoint_ecs().as_str(),
+            "https://ecs.cn-beijing.aliyuncs.com/"
+        );
+        assert_eq!(client.endpoint_sts().as_str(), "http://127.0.0.1:1/");
+
+        let client = Client::builder().build().unwrap();
+        assert_eq!(client.region(), None);
+        assert_eq!(client.endpoint_ecs().as_str(), "https://ecs.aliyuncs.com/");
+    }
+
+    #[tokio::test]
+    async fn from_env_signs_with_the_chain_and_uses_the_profile_region() {
+        let home = cli_home();
+        let env = Env::from_pairs(&[
+            ("HOME", home.to_str().unwrap()),
+            ("ALIBABA_CLOUD_ACCESS_KEY_ID", "env-id"),
+            ("ALIBABA_CLOUD_ACCESS_KEY_SECRET", "env-secret"),
+        ]);
+        let transport = Arc::new(MockAsyncTransport::new(vec![response(
+            StatusCode::OK,
+            HeaderMap::new(),
+            IDENTITY,
+        )]));
+
+        let client = ClientBuilder::from_env_with(env)
+            .transport(transport.clone())
+            .build()
+            .unwrap();
+        client.sts().get_caller_identity().await.unwrap();
+        std::fs::remove_dir_all(&home).unwrap();
+
+        assert_eq!(client.region().map(RegionId::as_str), Some("cn-shanghai"));
+        let url = transport.last_request().unwrap().url;
+        assert_eq!(url.host_str(), Some("sts.cn-shanghai.aliyuncs.com"));
+        assert!(url.query().unwrap().contains("AccessKeyId=env-id"));
+    }
+
+    #[tokio::test]
+    async fn from_env_reads_the_cli_config_once() {
+        let home = cli_home();
+        let env = Env::from_pairs(&[
+            ("HOME", home.to_str().unwrap()),
+            ("ALIBABA_CLOUD_ECS_METADATA_DISABLED", "true"),
+        ]);
+        let transport = Arc::new(MockAsyncTransport::new(vec![response(
+            StatusCode::OK,
+            HeaderMap::new(),
+            IDENTITY,
+        )]));
+
+        let client = ClientBuilder::from_env_with(env)
+            .transport(transport.clone())
+            .build()
+            .unwrap();
+        // The region and the credentials come from the file as first read.
+        std::fs::write(
+            home.join(".aliyun").join("config.json"),
+            r#"{"current":"dev","profiles":[{"name":"dev","mode":"AK","access_key_id":"new-id","access_key_secret":"new-secret","region_id":"cn-beijing"}]}"#,
+        )
+        .unwrap();
+        client.sts().get_caller_identity().await.unwrap();
+        std::fs::remove_dir_all(&home).unwrap();
+
+        assert_eq!(client.region().map(RegionId::as_str), Some("cn-shanghai"));
+        let url = transport.last_request().unwrap().url;
+        assert!(url.query().unwrap().contains("AccessKeyId=cli-id"));
+    }
+
+    #[test]
+    fn from_env_reports_an_unreadable_cli_config() {
+        let home = cli_home();
+        std::fs::write(home.join(".aliyun").join("config.json"), "not json").unwrap();
+        let env = Env::from_pairs(&[("HOME", home.to_str().unwrap())]);
+
+        let Err(err) = ClientBuilder::from_env_with(env).build() else {
+            panic!("an unreadable CLI config should fail the build");
+        };
+        std::fs::remove_dir_all(&home).unwrap();
+        assert!(
+            err.message().unwrap().contains("invalid aliyun CLI config"),
+            "{err}"
+        );
+
+        let missing = Env::from_pairs(&[("HOME", home.to_str().unwrap())]);
+        let client = ClientBuilder::from_env_with(missing).build().unwrap();
+        assert!(client.region().is_none());
+    }
 }
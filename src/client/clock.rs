@@ -0,0 +1,141 @@
+use std::{
+    sync::atomic::{AtomicI64, Ordering},
+    time::{Duration, SystemTime},
+};
+
+use http::{HeaderMap, header};
+
+use crate::error::Error;
+
+/// Skew changes smaller than this are ignored: `Date` only has seconds precision and
+/// also includes the response latency.
+const TOLERANCE_SECS: i64 = 30;
+
+/// Signing clock: the local clock corrected by the offset learned from the server's
+/// `Date` headers.
+#[derive(Debug, Default)]
+pub(crate) struct Clock {
+    offset_secs: AtomicI64,
+}
+
+impl Clock {
+    pub(crate) fn now(&self) -> SystemTime {
+        let offset = self.skew_secs();
+        let delta = Duration::from_secs(offset.unsigned_abs());
+        if offset >= 0 {
+            SystemTime::now() + delta
+        } else {
+            SystemTime::now() - delta
+        }
+    }
+
+    /// Seconds the server clock is ahead of the local clock (negative when behind).
+    pub(crate) fn skew_secs(&self) -> i64 {
+        self.offset_secs.load(Ordering::Relaxed)
+    }
+
+    /// Learn the skew from a response's `Date` header; returns whether it changed.
+    pub(crate) fn observe(&self, headers: &HeaderMap) -> bool {
+        let Some(server) = headers
+            .get(header::DATE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| httpdate::parse_http_date(value).ok())
+        else {
+            return false;
+        };
+        let skew = match server.duration_since(SystemTime::now()) {
+            Ok(ahead) => ahead.as_secs() as i64,
+            Err(behind) => -(behind.duration().as_secs() as i64),
+        };
+
+        let previous = self.skew_secs();
+        if (skew - previous).abs() < TOLERANCE_SECS {
+            return false;
+        }
+        self.offset_secs.store(skew, Ordering::Relaxed);
+        #[cfg(feature = "tracing")]
+        tracing::warn!(
+            skew_secs = skew,
+            previous_skew_secs = previous,
+            "local clock differs from server clock; adjusting request timestamps"
+        );
+        true
+    }
+
+    /// Whether a request signed when the skew was `signed_with` should be re-signed and
+    /// sent again: it failed on its timestamp and the skew has since been corrected.
+    pub(crate) fn should_retry(&self, signed_with: i64, error: &Error) -> bool {
+        let retry = is_skew_error(error) && self.skew_secs() != signed_with;
+        #[cfg(feature = "tracing")]
+        if retry {
+            tracing::debug!(
+                skew_secs = self.skew_secs(),
+                "retrying request with corrected timestamp"
+            );
+        }
+        retry
+    }
+}
+
+/// Errors the server returns when the request timestamp is too far from its clock.
+fn is_skew_error(error: &Error) -> bool {
+    matches!(
+        error.code(),
+        Some(
+            "InvalidTimeStamp.Expired"
+                | "InvalidTimeStamp.Format"
+                | "IllegalTimestamp"
+                | "RequestTimeTooSkewed"
+        )
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorInfo;
+
+    fn date_headers(at: SystemTime) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::DATE, httpdate::fmt_http_date(at).parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn observe_learns_offset_beyond_tolerance() {
+        let clock = Clock::default();
+        assert!(!clock.observe(&date_headers(SystemTime::now() + Duration::from_secs(5))));
+        assert_eq!(clock.skew_secs(), 0);
+
+        assert!(clock.observe(&date_headers(SystemTime::now() - Duration::from_secs(3600))));
+        assert!((clock.skew_secs() + 3600).abs() <= 2);
+        let behind = SystemTime::now().duration_since(clock.now()).unwrap();
+        assert!(behind >= Duration::from_secs(3598));
+
+        assert!(clock.observe(&date_headers(SystemTime::now())));
+        assert!(clock.skew_secs().abs() <= 2);
+    }
+
+    #[test]
+    fn observe_ignores_missing_or_invalid_date() {
+        let clock = Clock::default();
+        assert!(!clock.observe(&HeaderMap::new()));
+        let mut headers = HeaderMap::new();
+        headers.insert(header::DATE, "yesterday".parse().unwrap());
+        assert!(!clock.observe(&headers));
+        assert_eq!(clock.skew_secs(), 0);
+    }
+
+    #[test]
+    fn only_timestamp_errors_count_as_skew() {
+        let api_error = |code: &str| Error::Auth {
+            info: Box::new(ErrorInfo {
+                code: Some(code.to_owned()),
+                ..Default::default()
+            }),
+        };
+        assert!(is_skew_error(&api_error("InvalidTimeStamp.Expired")));
+        assert!(is_skew_error(&api_error("RequestTimeTooSkewed")));
+        assert!(!is_skew_error(&api_error("SignatureDoesNotMatch")));
+    }
+}
@@ -2,7 +2,8 @@ use http::{HeaderMap, Method, StatusCode, header};
 
 use crate::{
     error::{Error, ErrorInfo},
-    transport::{Response, retry::parse_retry_after},
+    transport::{Response, StreamingResponse, retry::parse_retry_after},
+    types::RegionId,
     util::redact,
 };
 
@@ -22,6 +23,33 @@ pub(crate) enum AliyunEnvelope<T> {
     Ok(T),
 }
 
+/// Status and headers of a buffered or streamed response, which decide whether it is
+/// retried.
+pub(crate) trait ResponseHead {
+    fn status(&self) -> StatusCode;
+    fn headers(&self) -> &HeaderMap;
+}
+
+impl ResponseHead for Response {
+    fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+}
+
+impl<B> ResponseHead for StreamingResponse<B> {
+    fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+}
+
 pub(crate) fn extract_request_id(headers: &HeaderMap) -> Option<String> {
     let names = [
         "x-acs-request-id",
@@ -41,6 +69,14 @@ pub(crate) fn extract_request_id(headers: &HeaderMap) -> Option<String> {
     None
 }
 
+/// Default endpoint of a regional product, falling back to the central endpoint.
+pub(crate) fn regional_endpoint(product: &str, region: Option<&RegionId>) -> String {
+    match region {
+        Some(region) => format!("https://{product}.{region}.aliyuncs.com/"),
+        None => format!("https://{product}.aliyuncs.com/"),
+    }
+}
+
 pub(crate) fn maybe_body_snippet(enabled: bool, body: &[u8], max_len: usize) -> Option<String> {
     if !enabled {
         return None;
@@ -56,13 +92,16 @@ pub(crate) fn classify_http_error(
     capture_body_snippet: bool,
     max_body_snippet_len: usize,
 ) -> Error {
-    let message = parse_aliyun_error_message(&response.body)
-        .or_else(|| Some(format!("http status {}", response.status)));
+    let (code, message) = match parse_aliyun_error(&response.body) {
+        Some((code, message)) => ((!code.is_empty()).then_some(code), Some(message)),
+        None => (None, Some(format!("http status {}", response.status))),
+    };
 
     let info = Box::new(ErrorInfo {
         status: Some(response.status),
         method: Some(method.clone()),
         path: Some(path),
+        code,
         message,
         request_id,
         body_snippet: maybe_body_snippet(
@@ -94,31 +133,47 @@ pub(crate) fn classify_aliyun_error(
 ) -> Error {
     let request_id = request_id.or_else(|| body.request_id.clone());
     let message = Some(format!("{}: {}", body.code, body.message));
+    let is_auth_error = is_auth_error_code(&body.code);
     let info = Box::new(ErrorInfo {
         status: Some(status),
         method: Some(method),
         path: Some(path),
+        code: Some(body.code),
         message,
         request_id,
         body_snippet,
     });
 
-    if is_auth_error_code(&body.code) {
+    if is_auth_error {
         return Error::Auth { info };
     }
 
     Error::Api { info }
 }
 
-fn parse_aliyun_error_message(body: &[u8]) -> Option<String> {
+/// The error code and `"{code}: {message}"` of an error body.
+fn parse_aliyun_error(body: &[u8]) -> Option<(String, String)> {
     let parsed: serde_json::Value = serde_json::from_slice(body).ok()?;
 
-    let code = parsed.get("Code")?.as_str()?.trim();
-    let message = parsed.get("Message")?.as_str()?.trim();
+    // RPC errors use `Code`/`Message`; ROA APIs use `code`/`message`.
+    let field = |upper: &str, lower: &str| {
+        parsed
+            .get(upper)
+            .or_else(|| parsed.get(lower))
+            .and_then(|value| value.as_str())
+            .map(str::trim)
+    };
+    let code = field("Code", "code")?;
+    let message = field("Message", "message")?;
     if code.is_empty() && message.is_empty() {
         return None;
     }
-    Some(format!("{code}: {message}"))
+    Some((code.to_owned(), format!("{code}: {message}")))
+}
+
+/// Whether the server rejected the request's STS token as expired.
+pub(crate) fn is_expired_token_error(error: &Error) -> bool {
+    error.code() == Some("InvalidSecurityToken.Expired")
 }
 
 fn is_auth_error_code(code: &str) -> bool {
@@ -128,6 +183,7 @@ fn is_auth_error_code(code: &str) -> bool {
             | "InvalidAccessKeyId"
             | "SignatureDoesNotMatch"
             | "InvalidSecurityToken"
+            | "InvalidSecurityToken.Expired"
             | "UnauthorizedOperation"
     )
 }
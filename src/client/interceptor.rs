@@ -0,0 +1,183 @@
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+
+use http::{HeaderMap, StatusCode};
+
+use crate::{error::Error, transport::Request};
+
+/// Hooks around the API calls a client makes, registered with `ClientBuilder::interceptor`
+/// or `BlockingClientBuilder::interceptor`.
+///
+/// Interceptors run in the order they were registered, and every hook defaults to doing
+/// nothing. A hook that returns an error fails the call without sending it.
+///
+/// ```
+/// use alibabacloud::client::{Interceptor, Operation};
+///
+/// struct ResourceGroup(String);
+///
+/// impl Interceptor for ResourceGroup {
+///     fn before_sign(&self, operation: &mut Operation) -> Result<(), alibabacloud::Error> {
+///         operation
+///             .params_mut()
+///             .insert("ResourceGroupId".to_owned(), self.0.clone());
+///         Ok(())
+///     }
+/// }
+/// ```
+pub trait Interceptor: Send + Sync {
+    /// Runs once per call before the request is first signed; changes to the parameters
+    /// are signed and sent.
+    fn before_sign(&self, operation: &mut Operation) -> Result<(), Error> {
+        let _ = operation;
+        Ok(())
+    }
+
+    /// Runs each time the request is signed (it is signed again after a clock skew is
+    /// corrected or expired credentials are refreshed) with the final URL and headers.
+    fn after_sign(&self, operation: &Operation, request: &Request) -> Result<(), Error> {
+        let _ = (operation, request);
+        Ok(())
+    }
+
+    /// Runs when a response arrives, after transport retries and before it is decoded,
+    /// whether or not it is successful.
+    fn after_response(&self, operation: &Operation, response: &ResponseMetadata<'_>) {
+        let _ = (operation, response);
+    }
+
+    /// Runs with the error a call finally fails with and returns the error to report.
+    fn on_error(&self, operation: &Operation, error: Error) -> Error {
+        let _ = operation;
+        error
+    }
+}
+
+/// The API call an [`Interceptor`] hook runs for.
+#[derive(Debug, Clone)]
+pub struct Operation {
+    action: Option<String>,
+    version: String,
+    params: BTreeMap<String, String>,
+    form_body: bool,
+}
+
+impl Operation {
+    pub(crate) fn new(
+        action: Option<String>,
+        version: impl Into<String>,
+        params: BTreeMap<String, String>,
+    ) -> Self {
+        Self {
+            action,
+            version: version.into(),
+            params,
+            form_body: false,
+        }
+    }
+
+    /// RPC action, or the `x-acs-action` of a ROA request if it has one.
+    pub fn action(&self) -> Option<&str> {
+        self.action.as_deref()
+    }
+
+    /// API version (e.g. `2014-05-26`).
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// RPC parameters, or the query parameters of a ROA request.
+    pub fn params(&self) -> &BTreeMap<String, String> {
+        &self.params
+    }
+
+    pub fn params_mut(&mut self) -> &mut BTreeMap<String, String> {
+        &mut self.params
+    }
+
+    /// Whether an RPC call is sent as a `POST` with its parameters in a form body even if
+    /// they would fit in the query string.
+    pub fn form_body(&self) -> bool {
+        self.form_body
+    }
+
+    /// Send an RPC call as a `POST` form (no effect on ROA requests).
+    pub fn set_form_body(&mut self, form_body: bool) {
+        self.form_body = form_body;
+    }
+}
+
+/// What [`Interceptor::after_response`] sees of a response.
+#[derive(Debug)]
+pub struct ResponseMetadata<'a> {
+    status: StatusCode,
+    headers: &'a HeaderMap,
+    request_id: Option<&'a str>,
+    elapsed: Duration,
+}
+
+impl<'a> ResponseMetadata<'a> {
+    pub(crate) fn new(
+        status: StatusCode,
+        headers: &'a HeaderMap,
+        request_id: Option<&'a str>,
+        elapsed: Duration,
+    ) -> Self {
+        Self {
+            status,
+            headers,
+            request_id,
+            elapsed,
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    pub fn headers(&self) -> &HeaderMap {
+        self.headers
+    }
+
+    pub fn request_id(&self) -> Option<&str> {
+        self.request_id
+    }
+
+    /// Time since the request was sent, including transport retries.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+}
+
+/// The interceptors registered on a client, run in order.
+#[derive(Clone, Default)]
+pub(crate) struct Interceptors(Vec<Arc<dyn Interceptor>>);
+
+impl Interceptors {
+    pub(crate) fn push(&mut self, interceptor: Arc<dyn Interceptor>) {
+        self.0.push(interceptor);
+    }
+
+    pub(crate) fn before_sign(&self, operation: &mut Operation) -> Result<(), Error> {
+        self.0
+            .iter()
+            .try_for_each(|interceptor| interceptor.before_sign(operation))
+    }
+
+    pub(crate) fn after_sign(&self, operation: &Operation, request: &Request) -> Result<(), Error> {
+        self.0
+            .iter()
+            .try_for_each(|interceptor| interceptor.after_sign(operation, request))
+    }
+
+    pub(crate) fn after_response(&self, operation: &Operation, response: &ResponseMetadata<'_>) {
+        for interceptor in &self.0 {
+            interceptor.after_response(operation, response);
+        }
+    }
+
+    pub(crate) fn on_error(&self, operation: &Operation, error: Error) -> Error {
+        self.0.iter().fold(error, |error, interceptor| {
+            interceptor.on_error(operation, error)
+        })
+    }
+}
@@ -1,4 +1,7 @@
+mod clock;
 mod common;
+mod interceptor;
+mod response;
 
 #[cfg(feature = "async")]
 mod async_client;
@@ -9,3 +12,9 @@ mod blocking_client;
 pub use async_client::{Client, ClientBuilder};
 #[cfg(feature = "blocking")]
 pub use blocking_client::{BlockingClient, BlockingClientBuilder};
+
+pub use interceptor::{Interceptor, Operation, ResponseMetadata};
+#[cfg(feature = "blocking")]
+pub use response::ResponseReader;
+#[cfg(feature = "async")]
+pub use response::ResponseStream;
@@ -0,0 +1,240 @@
+use std::{fmt, io};
+
+#[cfg(feature = "blocking")]
+use std::io::Read;
+#[cfg(feature = "async")]
+use std::{
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+#[cfg(feature = "async")]
+use bytes::Bytes;
+#[cfg(feature = "async")]
+use futures_core::Stream;
+use http::{HeaderMap, Method, StatusCode, header};
+
+#[cfg(feature = "blocking")]
+use crate::transport::BodyReader;
+#[cfg(feature = "async")]
+use crate::transport::BodyStream;
+use crate::{
+    error::{Error, ErrorInfo},
+    transport::{BoxError, StreamingResponse},
+};
+
+/// Status line and request ID of a streamed response, and what was requested.
+struct Head {
+    status: StatusCode,
+    headers: HeaderMap,
+    request_id: Option<String>,
+    method: Method,
+    path: String,
+}
+
+impl Head {
+    fn new<B>(
+        response: &mut StreamingResponse<B>,
+        request_id: Option<String>,
+        method: Method,
+        path: String,
+    ) -> Self {
+        Self {
+            status: response.status,
+            headers: std::mem::take(&mut response.headers),
+            request_id,
+            method,
+            path,
+        }
+    }
+
+    fn content_length(&self) -> Option<u64> {
+        self.headers
+            .get(header::CONTENT_LENGTH)?
+            .to_str()
+            .ok()?
+            .parse()
+            .ok()
+    }
+
+    /// A failure to read the body, as an [`Error::Transport`] carrying the request ID.
+    fn read_error(&self, source: BoxError) -> Error {
+        Error::Transport {
+            info: Box::new(ErrorInfo {
+                status: Some(self.status),
+                method: Some(self.method.clone()),
+                path: Some(self.path.clone()),
+                request_id: self.request_id.clone(),
+                ..ErrorInfo::default()
+            }),
+            source,
+        }
+    }
+
+    /// [`read_error`](Self::read_error) for `Read`/`AsyncRead`, keeping the error kind.
+    fn io_error(&self, source: BoxError) -> io::Error {
+        let kind = match source.downcast_ref::<io::Error>() {
+            Some(error) => error.kind(),
+            None => io::ErrorKind::Other,
+        };
+        io::Error::new(kind, self.read_error(source))
+    }
+
+    fn debug(&self, name: &str, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct(name)
+            .field("status", &self.status)
+            .field("request_id", &self.request_id)
+            .field("content_length", &self.content_length())
+            .finish_non_exhaustive()
+    }
+}
+
+/// A successful response whose body is read as it arrives, returned by
+/// [`Client::roa_stream`](crate::Client::roa_stream).
+///
+/// The body is a [`Stream`] of chunks and can also be read through
+/// [`tokio::io::AsyncRead`]. A failure to read it is an [`Error::Transport`] carrying the
+/// request ID; `AsyncRead` wraps it in an `io::Error`.
+#[cfg(feature = "async")]
+pub struct ResponseStream {
+    head: Head,
+    body: BodyStream,
+    /// Rest of the chunk being read through `AsyncRead`.
+    chunk: Bytes,
+}
+
+#[cfg(feature = "async")]
+impl ResponseStream {
+    pub(crate) fn new(
+        mut response: StreamingResponse<BodyStream>,
+        request_id: Option<String>,
+        method: Method,
+        path: String,
+    ) -> Self {
+        Self {
+            head: Head::new(&mut response, request_id, method, path),
+            body: response.body,
+            chunk: Bytes::new(),
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.head.status
+    }
+
+    pub fn headers(&self) -> &HeaderMap {
+        &self.head.headers
+    }
+
+    /// Request ID from the response headers.
+    pub fn request_id(&self) -> Option<&str> {
+        self.head.request_id.as_deref()
+    }
+
+    /// Length of the body from `Content-Length`, if the server sent one.
+    pub fn content_length(&self) -> Option<u64> {
+        self.head.content_length()
+    }
+}
+
+#[cfg(feature = "async")]
+impl Stream for ResponseStream {
+    type Item = Result<Bytes, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if !this.chunk.is_empty() {
+            return Poll::Ready(Some(Ok(std::mem::take(&mut this.chunk))));
+        }
+        let item = ready!(this.body.as_mut().poll_next(cx));
+        Poll::Ready(item.map(|chunk| chunk.map_err(|e| this.head.read_error(e))))
+    }
+}
+
+#[cfg(feature = "async")]
+impl tokio::io::AsyncRead for ResponseStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        while this.chunk.is_empty() {
+            match ready!(this.body.as_mut().poll_next(cx)) {
+                Some(Ok(chunk)) => this.chunk = chunk,
+                Some(Err(e)) => return Poll::Ready(Err(this.head.io_error(e))),
+                None => return Poll::Ready(Ok(())),
+            }
+        }
+        let len = buf.remaining().min(this.chunk.len());
+        buf.put_slice(&this.chunk.split_to(len));
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(feature = "async")]
+impl fmt::Debug for ResponseStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.head.debug("ResponseStream", f)
+    }
+}
+
+/// A successful response whose body is read as it arrives, returned by
+/// [`BlockingClient::roa_stream`](crate::BlockingClient::roa_stream).
+///
+/// A failure to read the body is an `io::Error` wrapping an [`Error::Transport`] that
+/// carries the request ID.
+#[cfg(feature = "blocking")]
+pub struct ResponseReader {
+    head: Head,
+    body: BodyReader,
+}
+
+#[cfg(feature = "blocking")]
+impl ResponseReader {
+    pub(crate) fn new(
+        mut response: StreamingResponse<BodyReader>,
+        request_id: Option<String>,
+        method: Method,
+        path: String,
+    ) -> Self {
+        Self {
+            head: Head::new(&mut response, request_id, method, path),
+            body: response.body,
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.head.status
+    }
+
+    pub fn headers(&self) -> &HeaderMap {
+        &self.head.headers
+    }
+
+    /// Request ID from the response headers.
+    pub fn request_id(&self) -> Option<&str> {
+        self.head.request_id.as_deref()
+    }
+
+    /// Length of the body from `Content-Length`, if the server sent one.
+    pub fn content_length(&self) -> Option<u64> {
+        self.head.content_length()
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl Read for ResponseReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.body
+            .read(buf)
+            .map_err(|e| self.head.io_error(Box::new(e)))
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl fmt::Debug for ResponseReader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.head.debug("ResponseReader", f)
+    }
+}
@@ -12,7 +12,7 @@ pub enum Error {
         source: Option<Box<dyn StdError + Send + Sync + 'static>>,
     },
 
-    /// Authentication errors (401/403, or API-level auth failures).
+    /// Authentication errors (401/403, API-level auth failures, or credential resolution failures).
     Auth { info: Box<ErrorInfo> },
 
     /// Resource not found (404).
@@ -49,6 +49,7 @@ pub struct ErrorInfo {
     pub(crate) status: Option<StatusCode>,
     pub(crate) method: Option<Method>,
     pub(crate) path: Option<String>,
+    pub(crate) code: Option<String>,
     pub(crate) message: Option<String>,
     pub(crate) request_id: Option<String>,
     pub(crate) body_snippet: Option<String>,
@@ -65,6 +66,15 @@ impl Error {
         }
     }
 
+    pub(crate) fn auth(message: impl Into<String>) -> Self {
+        Self::Auth {
+            info: Box::new(ErrorInfo {
+                message: Some(message.into()),
+                ..ErrorInfo::default()
+            }),
+        }
+    }
+
     pub fn status(&self) -> Option<StatusCode> {
         self.info().and_then(|info| info.status)
     }
@@ -77,6 +87,11 @@ impl Error {
         self.info().and_then(|info| info.body_snippet.as_deref())
     }
 
+    /// Aliyun error code of the response (e.g. `InvalidTimeStamp.Expired`), if any.
+    pub fn code(&self) -> Option<&str> {
+        self.info().and_then(|info| info.code.as_deref())
+    }
+
     pub fn message(&self) -> Option<&str> {
         self.info().and_then(|info| info.message.as_deref())
     }
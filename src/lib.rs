@@ -58,13 +58,13 @@ compile_error!("Enable only one TLS backend: `rustls` or `native-tls`");
 ))]
 compile_error!("Enable one TLS backend: `native-tls` (default) or `rustls`");
 
-mod transport;
 mod util;
 
 pub mod api;
 pub mod auth;
 pub mod client;
 pub mod error;
+pub mod transport;
 pub mod types;
 
 pub use auth::Auth;
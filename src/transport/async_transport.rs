@@ -1,6 +1,7 @@
-use std::{future::Future, pin::Pin, time::Duration};
-
-use crate::transport::{AsyncTransport, BoxError, Request, Response};
+use crate::transport::{
+    AsyncTransport, BodyStream, BoxError, ConnectionConfig, Payload, Proxy, Request, Response,
+    StreamingResponse, StreamingTransportFuture, SyncStream, TransportFuture,
+};
 
 #[cfg(feature = "native-tls")]
 pub(crate) struct ReqwestTransport {
@@ -9,71 +10,305 @@ pub(crate) struct ReqwestTransport {
 
 #[cfg(feature = "native-tls")]
 impl ReqwestTransport {
-    pub(crate) fn new(connect_timeout: Duration) -> Result<Self, BoxError> {
-        let client = reqwest::Client::builder()
-            .connect_timeout(connect_timeout)
-            .build()?;
-        Ok(Self { client })
+    pub(crate) fn new(config: &ConnectionConfig, proxy: Option<&Proxy>) -> Result<Self, BoxError> {
+        let mut builder = reqwest::Client::builder()
+            .connect_timeout(config.connect_timeout)
+            .tcp_keepalive(config.tcp_keepalive)
+            .no_proxy();
+        if let Some(proxy) = proxy {
+            builder = builder.proxy(reqwest_proxy(proxy)?);
+        }
+        if let Some(max_idle) = config.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max_idle);
+        }
+        if let Some(timeout) = config.pool_idle_timeout {
+            builder = builder.pool_idle_timeout(timeout);
+        }
+        if !config.http2 {
+            builder = builder.http1_only();
+        }
+
+        let tls = &config.tls;
+        tls.validate()?;
+        let roots = tls
+            .root_certs
+            .iter()
+            .map(|cert| reqwest::Certificate::from_der(cert.der()))
+            .collect::<Result<Vec<_>, _>>()?;
+        builder = if tls.built_in_roots {
+            builder.tls_certs_merge(roots)
+        } else {
+            builder.tls_certs_only(roots)
+        };
+        if let Some(identity) = &tls.identity {
+            builder = builder.identity(reqwest::Identity::from_pkcs8_pem(
+                identity.certs_pem().as_bytes(),
+                identity.key_pem().as_bytes(),
+            )?);
+        }
+
+        Ok(Self {
+            client: builder.build()?,
+        })
+    }
+}
+
+#[cfg(feature = "native-tls")]
+fn reqwest_proxy(proxy: &Proxy) -> Result<reqwest::Proxy, reqwest::Error> {
+    let mut reqwest_proxy = reqwest::Proxy::all(proxy.url().as_str())?.no_proxy(
+        proxy
+            .no_proxy_list()
+            .and_then(reqwest::NoProxy::from_string),
+    );
+    if let Some(username) = proxy.username() {
+        let password = proxy
+            .password()
+            .map(|p| p.expose_secret())
+            .unwrap_or_default();
+        reqwest_proxy = reqwest_proxy.basic_auth(username, password);
     }
+    Ok(reqwest_proxy)
 }
 
 #[cfg(feature = "rustls")]
 pub(crate) struct HyperRustlsTransport {
     client: hyper_util::client::legacy::Client<
-        hyper_rustls::HttpsConnector<hyper_util::client::legacy::connect::HttpConnector>,
-        http_body_util::Empty<hyper::body::Bytes>,
+        hyper_rustls::HttpsConnector<ProxyConnector>,
+        http_body_util::combinators::BoxBody<hyper::body::Bytes, BoxError>,
     >,
 }
 
 #[cfg(feature = "rustls")]
 impl HyperRustlsTransport {
-    pub(crate) fn new(connect_timeout: Duration) -> Result<Self, BoxError> {
-        use std::sync::Arc;
-
+    pub(crate) fn new(config: &ConnectionConfig, proxy: Option<&Proxy>) -> Result<Self, BoxError> {
         use hyper_util::client::legacy::connect::HttpConnector;
-        use hyper_util::rt::TokioExecutor;
+        use hyper_util::rt::{TokioExecutor, TokioTimer};
 
         let mut http = HttpConnector::new();
-        http.set_connect_timeout(Some(connect_timeout));
+        http.set_connect_timeout(Some(config.connect_timeout));
+        http.set_keepalive(config.tcp_keepalive);
         http.enforce_http(false);
 
         let https = hyper_rustls::HttpsConnectorBuilder::new()
-            .with_provider_and_platform_verifier(
-                Arc::new(rustls::crypto::ring::default_provider()),
-            )?
-            .https_or_http()
-            .enable_http1()
-            .wrap_connector(http);
+            .with_tls_config(rustls_config(&config.tls)?)
+            .https_or_http();
+        let connector = ProxyConnector::new(http, proxy)?;
+        let https = if config.http2 {
+            https.enable_all_versions().wrap_connector(connector)
+        } else {
+            https.enable_http1().wrap_connector(connector)
+        };
 
-        let client = hyper_util::client::legacy::Client::builder(TokioExecutor::new()).build(https);
+        let mut builder = hyper_util::client::legacy::Client::builder(TokioExecutor::new());
+        builder.pool_timer(TokioTimer::new());
+        if let Some(max_idle) = config.pool_max_idle_per_host {
+            builder.pool_max_idle_per_host(max_idle);
+        }
+        if let Some(timeout) = config.pool_idle_timeout {
+            builder.pool_idle_timeout(timeout);
+        }
+        let client = builder.build(https);
 
         Ok(Self { client })
     }
 }
 
+/// Client config verifying servers with the platform verifier, extended with or replaced
+/// by the configured roots.
 #[cfg(feature = "rustls")]
-impl AsyncTransport for HyperRustlsTransport {
-    fn send<'a>(
-        &'a self,
-        request: Request,
-    ) -> Pin<Box<dyn Future<Output = Result<Response, BoxError>> + Send + 'a>> {
-        use http_body_util::BodyExt;
+fn rustls_config(tls: &crate::transport::TlsConfig) -> Result<rustls::ClientConfig, BoxError> {
+    use std::sync::Arc;
 
-        Box::pin(async move {
-            let timeout = request.timeout;
+    tls.validate()?;
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let roots = tls.root_certs.iter().map(|cert| cert.der().clone());
+    let builder = rustls::ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()?;
+    let builder = if tls.built_in_roots {
+        #[cfg(not(target_os = "android"))]
+        let verifier = rustls_platform_verifier::Verifier::new_with_extra_roots(roots, provider)?;
+        #[cfg(target_os = "android")]
+        let verifier = match roots.len() {
+            0 => rustls_platform_verifier::Verifier::new(provider)?,
+            _ => return Err("extra root certificates are not supported on Android".into()),
+        };
+        builder
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(verifier))
+    } else {
+        let mut store = rustls::RootCertStore::empty();
+        for root in roots {
+            store.add(root)?;
+        }
+        builder.with_root_certificates(store)
+    };
+
+    Ok(match &tls.identity {
+        Some(identity) => builder.with_client_auth_cert(
+            identity.certs().iter().map(|c| c.der().clone()).collect(),
+            identity.key().clone_key(),
+        )?,
+        None => builder.with_no_client_auth(),
+    })
+}
+
+/// Connects to the endpoint directly or, unless it is excluded, through the proxy: a
+/// `CONNECT` tunnel for `http://` proxies, a SOCKS5 handshake otherwise. TLS to the
+/// endpoint is layered on top by `hyper-rustls`.
+#[cfg(feature = "rustls")]
+#[derive(Clone)]
+pub(crate) struct ProxyConnector {
+    http: hyper_util::client::legacy::connect::HttpConnector,
+    proxy: Option<std::sync::Arc<ProxyRoute>>,
+}
+
+#[cfg(feature = "rustls")]
+struct ProxyRoute {
+    matcher: hyper_util::client::proxy::matcher::Matcher,
+    proxy: Proxy,
+    /// `Proxy-Authorization` for the `CONNECT` request.
+    authorization: Option<http::HeaderValue>,
+}
+
+#[cfg(feature = "rustls")]
+type Connecting = std::pin::Pin<
+    Box<
+        dyn std::future::Future<
+                Output = Result<hyper_util::rt::TokioIo<tokio::net::TcpStream>, BoxError>,
+            > + Send,
+    >,
+>;
+
+#[cfg(feature = "rustls")]
+impl ProxyConnector {
+    fn new(
+        http: hyper_util::client::legacy::connect::HttpConnector,
+        proxy: Option<&Proxy>,
+    ) -> Result<Self, BoxError> {
+        let Some(proxy) = proxy else {
+            return Ok(Self { http, proxy: None });
+        };
+        let matcher = hyper_util::client::proxy::matcher::Matcher::builder()
+            .all(proxy.url().to_string())
+            .no(proxy.no_proxy_list().unwrap_or_default())
+            .build();
+        let authorization = match proxy.username() {
+            Some(username) => Some(basic_auth(username, proxy.password())?),
+            None => None,
+        };
+        let proxy = Some(std::sync::Arc::new(ProxyRoute {
+            matcher,
+            proxy: proxy.clone(),
+            authorization,
+        }));
+        Ok(Self { http, proxy })
+    }
+}
 
-            let mut builder = http::Request::builder()
-                .method(request.method)
-                .uri(request.url.as_str());
+#[cfg(feature = "rustls")]
+impl tower_service::Service<http::Uri> for ProxyConnector {
+    type Response = hyper_util::rt::TokioIo<tokio::net::TcpStream>;
+    type Error = BoxError;
+    type Future = Connecting;
+
+    fn poll_ready(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        self.http.poll_ready(cx).map_err(Into::into)
+    }
 
-            for (name, value) in request.headers.iter() {
-                builder = builder.header(name, value);
+    fn call(&mut self, dst: http::Uri) -> Self::Future {
+        use hyper_util::client::legacy::connect::proxy::{SocksV5, Tunnel};
+
+        let Some((route, intercept)) = self
+            .proxy
+            .as_ref()
+            .and_then(|route| Some((route, route.matcher.intercept(&dst)?)))
+        else {
+            let connecting = self.http.call(dst);
+            return Box::pin(async move { Ok(connecting.await?) });
+        };
+
+        let proxy = &route.proxy;
+        let proxy_uri = intercept.uri().clone();
+        if proxy.url().scheme() == "http" {
+            let mut tunnel = Tunnel::new(proxy_uri, self.http.clone());
+            if let Some(authorization) = &route.authorization {
+                tunnel = tunnel.with_auth(authorization.clone());
+            }
+            Box::pin(async move { Ok(tunnel.call(dst).await?) })
+        } else {
+            let mut socks =
+                SocksV5::new(proxy_uri, self.http.clone()).local_dns(!proxy.resolves_host());
+            if let Some(username) = proxy.username() {
+                let password = proxy
+                    .password()
+                    .map(|p| p.expose_secret())
+                    .unwrap_or_default();
+                socks = socks.with_auth(username.to_owned(), password.to_owned());
             }
+            Box::pin(async move { Ok(socks.call(dst).await?) })
+        }
+    }
+}
+
+#[cfg(feature = "rustls")]
+fn basic_auth(
+    username: &str,
+    password: Option<&crate::auth::SecretString>,
+) -> Result<http::HeaderValue, BoxError> {
+    use base64::Engine;
+
+    let password = password.map(|p| p.expose_secret()).unwrap_or_default();
+    let encoded =
+        base64::engine::general_purpose::STANDARD.encode(format!("{username}:{password}"));
+    let mut value = http::HeaderValue::try_from(format!("Basic {encoded}"))?;
+    value.set_sensitive(true);
+    Ok(value)
+}
+
+#[cfg(feature = "rustls")]
+impl HyperRustlsTransport {
+    /// Send `request` and wait for the response headers, within the request timeout.
+    async fn exchange(
+        &self,
+        mut request: Request,
+    ) -> Result<http::Response<hyper::body::Incoming>, BoxError> {
+        use http_body_util::{BodyExt, Full, StreamBody};
+
+        let timeout = request.timeout;
+        request.set_content_length();
+
+        let mut builder = http::Request::builder()
+            .method(request.method)
+            .uri(request.url.as_str());
 
-            let http_request = builder.body(http_body_util::Empty::new())?;
+        for (name, value) in request.headers.iter() {
+            builder = builder.header(name, value);
+        }
 
-            let response =
-                tokio::time::timeout(timeout, self.client.request(http_request)).await??;
+        let body = match request.body.into_payload()? {
+            Payload::Bytes(bytes) => Full::new(bytes.into())
+                .map_err(|never| match never {})
+                .boxed(),
+            Payload::Stream(stream) => StreamBody::new(SyncStream::new(stream)).boxed(),
+            #[cfg(feature = "blocking")]
+            Payload::Reader(_) => return Err("a reader body needs the blocking client".into()),
+        };
+        let http_request = builder.body(body)?;
+
+        Ok(tokio::time::timeout(timeout, self.client.request(http_request)).await??)
+    }
+}
+
+#[cfg(feature = "rustls")]
+impl AsyncTransport for HyperRustlsTransport {
+    fn send(&self, request: Request) -> TransportFuture<'_> {
+        use http_body_util::BodyExt;
+
+        Box::pin(async move {
+            let response = self.exchange(request).await?;
 
             let status = response.status();
             let headers = response.headers().clone();
@@ -86,26 +321,69 @@ impl AsyncTransport for HyperRustlsTransport {
             })
         })
     }
+
+    fn send_streaming(&self, request: Request) -> StreamingTransportFuture<'_> {
+        use http_body_util::BodyExt;
+
+        Box::pin(async move {
+            let (parts, body) = self.exchange(request).await?.into_parts();
+            let body: BodyStream = Box::pin(body.map_err(BoxError::from).into_data_stream());
+
+            Ok(StreamingResponse {
+                status: parts.status,
+                headers: parts.headers,
+                body,
+            })
+        })
+    }
+}
+
+#[cfg(feature = "native-tls")]
+impl ReqwestTransport {
+    /// The request without a timeout, which differs between buffered and streamed
+    /// responses.
+    fn request(&self, mut request: Request) -> Result<reqwest::RequestBuilder, BoxError> {
+        request.set_content_length();
+        let mut builder = self
+            .client
+            .request(request.method, request.url)
+            .headers(request.headers);
+        match request.body.into_payload()? {
+            Payload::Bytes(bytes) if bytes.is_empty() => {}
+            Payload::Bytes(bytes) => builder = builder.body(bytes),
+            Payload::Stream(stream) => {
+                builder = builder.body(reqwest::Body::wrap(http_body_util::StreamBody::new(
+                    SyncStream::new(stream),
+                )));
+            }
+            #[cfg(feature = "blocking")]
+            Payload::Reader(_) => return Err("a reader body needs the blocking client".into()),
+        }
+        Ok(builder)
+    }
 }
 
 #[cfg(feature = "native-tls")]
 impl AsyncTransport for ReqwestTransport {
-    fn send<'a>(
-        &'a self,
-        request: Request,
-    ) -> Pin<Box<dyn Future<Output = Result<Response, BoxError>> + Send + 'a>> {
+    fn send(&self, request: Request) -> TransportFuture<'_> {
         Box::pin(async move {
+            let timeout = request.timeout;
             let response = self
-                .client
-                .request(request.method, request.url)
-                .headers(request.headers)
-                .timeout(request.timeout)
+                .request(request)?
+                .timeout(timeout)
                 .send()
-                .await?;
+                .await
+                // The URL carries the signed query (and e.g. SAML assertions); keep it out
+                // of error messages.
+                .map_err(reqwest::Error::without_url)?;
 
             let status = response.status();
             let headers = response.headers().clone();
-            let body = response.bytes().await?.to_vec();
+            let body = response
+                .bytes()
+                .await
+                .map_err(reqwest::Error::without_url)?
+                .to_vec();
 
             Ok(Response {
                 status,
@@ -114,4 +392,46 @@ impl AsyncTransport for ReqwestTransport {
             })
         })
     }
+
+    fn send_streaming(&self, request: Request) -> StreamingTransportFuture<'_> {
+        Box::pin(async move {
+            let timeout = request.timeout;
+            // `reqwest`'s own timeout would also cover reading the body.
+            let response = tokio::time::timeout(timeout, self.request(request)?.send())
+                .await?
+                .map_err(reqwest::Error::without_url)?;
+
+            let status = response.status();
+            let headers = response.headers().clone();
+            let body: BodyStream = Box::pin(ReqwestBody(Box::pin(response.bytes_stream())));
+
+            Ok(StreamingResponse {
+                status,
+                headers,
+                body,
+            })
+        })
+    }
+}
+
+/// A `reqwest` body stream with URL-free errors.
+#[cfg(feature = "native-tls")]
+struct ReqwestBody<S>(std::pin::Pin<Box<S>>);
+
+#[cfg(feature = "native-tls")]
+impl<S> futures_core::Stream for ReqwestBody<S>
+where
+    S: futures_core::Stream<Item = reqwest::Result<bytes::Bytes>>,
+{
+    type Item = Result<bytes::Bytes, BoxError>;
+
+    fn poll_next(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        self.0
+            .as_mut()
+            .poll_next(cx)
+            .map(|item| item.map(|chunk| chunk.map_err(|e| reqwest::Error::without_url(e).into())))
+    }
 }
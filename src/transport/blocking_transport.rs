@@ -1,26 +1,83 @@
-use std::time::Duration;
+use std::{net::IpAddr, time::Duration};
 
-use crate::transport::{BlockingTransport, BoxError, Request, Response};
+use crate::transport::{
+    BlockingTransport, BodyReader, BoxError, ConnectionConfig, Payload, Proxy, Request, Response,
+    StreamingResponse, TlsConfig,
+};
 
+/// Blocking transport sharing one `ureq` agent, and so its connection pool, across requests.
 pub(crate) struct UreqTransport {
-    connect_timeout: Duration,
+    agent: ureq::Agent,
+    /// `NO_PROXY` CIDR ranges, which `ureq` cannot match itself.
+    no_proxy_ranges: Vec<IpRange>,
 }
 
 impl UreqTransport {
-    pub(crate) fn new(connect_timeout: Duration) -> Result<Self, BoxError> {
-        Ok(Self { connect_timeout })
+    pub(crate) fn new(config: &ConnectionConfig, proxy: Option<&Proxy>) -> Result<Self, BoxError> {
+        let no_proxy_ranges = proxy
+            .and_then(Proxy::no_proxy_list)
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|entry| IpRange::parse(entry.trim()))
+            .collect();
+        let proxy = proxy.map(ureq_proxy).transpose()?;
+        let mut builder = ureq::Agent::config_builder()
+            .http_status_as_error(false)
+            .timeout_connect(Some(config.connect_timeout))
+            .proxy(proxy)
+            .tls_config(ureq_tls(&config.tls)?);
+        if let Some(max_idle) = config.pool_max_idle {
+            builder = builder.max_idle_connections(max_idle);
+        }
+        if let Some(max_idle) = config.pool_max_idle_per_host {
+            builder = builder.max_idle_connections_per_host(max_idle);
+        }
+        if let Some(timeout) = config.pool_idle_timeout {
+            builder = builder.max_idle_age(timeout);
+        }
+        Ok(Self {
+            agent: ureq::Agent::new_with_config(builder.build()),
+            no_proxy_ranges,
+        })
     }
-}
 
-impl BlockingTransport for UreqTransport {
-    fn send(&self, request: Request) -> Result<Response, BoxError> {
-        let config = ureq::Agent::config_builder()
-            .http_status_as_error(false)
-            .timeout_connect(Some(self.connect_timeout))
-            .timeout_global(Some(request.timeout))
-            .build();
-        let agent = ureq::Agent::new_with_config(config);
+    /// Send `request`; `timeout` covers the whole exchange, or only until the response
+    /// headers arrive when the body is `streamed`.
+    fn run<B: ureq::AsSendBody>(
+        &self,
+        request: http::Request<B>,
+        timeout: Duration,
+        streamed: bool,
+    ) -> Result<http::Response<ureq::Body>, ureq::Error> {
+        let direct = self.bypasses_proxy(request.uri());
+        let config = self.agent.configure_request(request);
+        let config = if streamed {
+            config.timeout_recv_response(Some(timeout))
+        } else {
+            config.timeout_global(Some(timeout))
+        };
+        let config = if direct { config.proxy(None) } else { config };
+        self.agent.run(config.build())
+    }
 
+    /// Whether the host of `uri` is an IP address in a `NO_PROXY` CIDR range.
+    fn bypasses_proxy(&self, uri: &http::Uri) -> bool {
+        let Some(ip) = uri
+            .host()
+            .map(|host| host.trim_start_matches('[').trim_end_matches(']'))
+            .and_then(|host| host.parse::<IpAddr>().ok())
+        else {
+            return false;
+        };
+        self.no_proxy_ranges.iter().any(|range| range.contains(ip))
+    }
+
+    fn exchange(
+        &self,
+        mut request: Request,
+        streamed: bool,
+    ) -> Result<http::Response<ureq::Body>, BoxError> {
+        request.set_content_length();
         let mut builder = http::Request::builder()
             .method(request.method)
             .uri(request.url.as_str());
@@ -29,8 +86,92 @@ impl BlockingTransport for UreqTransport {
             builder = builder.header(name, value);
         }
 
-        let request = builder.body(())?;
-        let mut response = agent.run(request)?;
+        let timeout = request.timeout;
+        Ok(match request.body.into_payload()? {
+            Payload::Bytes(bytes) if bytes.is_empty() => {
+                self.run(builder.body(())?, timeout, streamed)?
+            }
+            Payload::Bytes(bytes) => self.run(builder.body(bytes)?, timeout, streamed)?,
+            Payload::Reader(reader) => self.run(
+                builder.body(ureq::SendBody::from_owned_reader(reader))?,
+                timeout,
+                streamed,
+            )?,
+            #[cfg(feature = "async")]
+            Payload::Stream(_) => return Err("a streaming body needs the async client".into()),
+        })
+    }
+}
+
+/// Keeps `ureq`'s Mozilla roots unless they are disabled, adding the configured ones.
+fn ureq_tls(tls: &TlsConfig) -> Result<ureq::tls::TlsConfig, BoxError> {
+    use ureq::tls::{Certificate, ClientCert, PrivateKey, RootCerts};
+
+    tls.validate()?;
+    let mut builder = ureq::tls::TlsConfig::builder();
+    // `ureq` defaults to rustls even when only its native-tls backend is compiled in.
+    #[cfg(feature = "native-tls")]
+    {
+        builder = builder.provider(ureq::tls::TlsProvider::NativeTls);
+    }
+    if !tls.root_certs.is_empty() {
+        let built_in = webpki_root_certs::TLS_SERVER_ROOT_CERTS
+            .iter()
+            .filter(|_| tls.built_in_roots);
+        let roots = built_in
+            .chain(tls.root_certs.iter().map(|cert| cert.der()))
+            .map(|der| Certificate::from_der(der).to_owned());
+        builder = builder.root_certs(RootCerts::from(roots));
+    }
+    if let Some(identity) = &tls.identity {
+        let certs = identity
+            .certs()
+            .iter()
+            .map(|cert| Certificate::from_der(cert.der()).to_owned())
+            .collect::<Vec<_>>();
+        let key = PrivateKey::from_pem(identity.key_pem().as_bytes())?;
+        builder = builder.client_cert(Some(ClientCert::new_with_certs(&certs, key)));
+    }
+    Ok(builder.build())
+}
+
+fn ureq_proxy(proxy: &Proxy) -> Result<ureq::Proxy, ureq::Error> {
+    let url = proxy.url();
+    let protocol = match url.scheme() {
+        "http" => ureq::ProxyProtocol::Http,
+        _ => ureq::ProxyProtocol::Socks5,
+    };
+    let mut builder = ureq::Proxy::builder(protocol)
+        .host(url.host_str().unwrap_or_default())
+        .resolve_target(!proxy.resolves_host());
+    if let Some(port) = url.port_or_known_default() {
+        builder = builder.port(port);
+    }
+    if let Some(username) = proxy.username() {
+        builder = builder.username(username);
+    }
+    if let Some(password) = proxy.password() {
+        builder = builder.password(password.expose_secret());
+    }
+    for host in proxy.no_proxy_list().unwrap_or_default().split(',') {
+        let host = host.trim();
+        // CIDR ranges are matched by `UreqTransport::bypasses_proxy`.
+        if host.is_empty() || IpRange::parse(host).is_some() {
+            continue;
+        }
+        builder = builder.no_proxy(host);
+        // ureq matches a plain host name exactly; the other transports also match its
+        // subdomains.
+        if !host.starts_with(['.', '*']) && host.parse::<std::net::IpAddr>().is_err() {
+            builder = builder.no_proxy(&format!(".{host}"));
+        }
+    }
+    builder.build()
+}
+
+impl BlockingTransport for UreqTransport {
+    fn send(&self, request: Request) -> Result<Response, BoxError> {
+        let mut response = self.exchange(request, false)?;
 
         let status = response.status();
         let headers = response.headers().clone();
@@ -43,4 +184,71 @@ impl BlockingTransport for UreqTransport {
             body,
         })
     }
+
+    fn send_streaming(&self, request: Request) -> Result<StreamingResponse<BodyReader>, BoxError> {
+        let (parts, body) = self.exchange(request, true)?.into_parts();
+
+        Ok(StreamingResponse {
+            status: parts.status,
+            headers: parts.headers,
+            body: Box::new(body.into_reader()),
+        })
+    }
+}
+
+/// An IP network such as `10.0.0.0/8` or `fd00::/8`.
+struct IpRange {
+    network: IpAddr,
+    prefix_len: u32,
+}
+
+impl IpRange {
+    fn parse(s: &str) -> Option<Self> {
+        let (network, prefix_len) = s.split_once('/')?;
+        let network = network.parse::<IpAddr>().ok()?;
+        let prefix_len = prefix_len.parse::<u32>().ok()?;
+        let max_len = if network.is_ipv4() { 32 } else { 128 };
+        (prefix_len <= max_len).then_some(Self {
+            network,
+            prefix_len,
+        })
+    }
+
+    fn contains(&self, ip: IpAddr) -> bool {
+        match (self.network, ip) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => (u32::from(network) ^ u32::from(ip))
+                .checked_shr(32 - self.prefix_len)
+                .is_none_or(|diff| diff == 0),
+            (IpAddr::V6(network), IpAddr::V6(ip)) => (u128::from(network) ^ u128::from(ip))
+                .checked_shr(128 - self.prefix_len)
+                .is_none_or(|diff| diff == 0),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ip_ranges_match_their_network_only() {
+        let range = IpRange::parse("10.0.0.0/8").unwrap();
+        assert!(range.contains("10.255.0.1".parse().unwrap()));
+        assert!(!range.contains("11.0.0.1".parse().unwrap()));
+        assert!(!range.contains("::ffff:10.0.0.1".parse().unwrap()));
+
+        let range = IpRange::parse("fd00::/8").unwrap();
+        assert!(range.contains("fd12::1".parse().unwrap()));
+        assert!(!range.contains("fe80::1".parse().unwrap()));
+
+        assert!(
+            IpRange::parse("0.0.0.0/0")
+                .unwrap()
+                .contains("1.2.3.4".parse().unwrap())
+        );
+        assert!(IpRange::parse("10.0.0.0/33").is_none());
+        assert!(IpRange::parse("proxy.internal/8").is_none());
+        assert!(IpRange::parse("10.0.0.1").is_none());
+    }
 }
@@ -0,0 +1,350 @@
+use std::{
+    fmt,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+#[cfg(feature = "blocking")]
+use std::io::Read;
+#[cfg(feature = "async")]
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+#[cfg(feature = "async")]
+use bytes::Bytes;
+#[cfg(feature = "async")]
+use futures_core::Stream;
+
+use super::BoxError;
+
+/// Chunks of a streaming [`Body`] or [`StreamingResponse`](super::StreamingResponse).
+#[cfg(feature = "async")]
+pub type BodyStream = Pin<Box<dyn Stream<Item = Result<Bytes, BoxError>> + Send>>;
+
+/// Body of a blocking [`StreamingResponse`](super::StreamingResponse).
+#[cfg(feature = "blocking")]
+pub type BodyReader = Box<dyn Read + Send>;
+
+/// Body of a [`Request`](super::Request): bytes, or a stream that is read once.
+///
+/// A streaming body is sent with the `Content-Length` it was created with, or with
+/// chunked encoding when its length is unknown. It can only be sent once, so requests
+/// carrying one are not retried; clones share the same stream. `Content-Type` comes
+/// from the request headers.
+#[derive(Clone, Default)]
+pub struct Body(Kind);
+
+#[derive(Clone)]
+enum Kind {
+    Bytes(Vec<u8>),
+    #[cfg(feature = "async")]
+    Stream {
+        stream: Once<BodyStream>,
+        len: Option<u64>,
+    },
+    #[cfg(feature = "blocking")]
+    Reader {
+        reader: Once<Box<dyn Read + Send>>,
+        len: Option<u64>,
+    },
+}
+
+impl Default for Kind {
+    fn default() -> Self {
+        Self::Bytes(Vec::new())
+    }
+}
+
+/// A value that can be taken by only one of the clones holding it.
+type Once<T> = Arc<Mutex<Option<T>>>;
+
+fn take<T>(once: &Once<T>) -> Result<T, BoxError> {
+    once.lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .take()
+        .ok_or_else(|| "streaming request body was already sent".into())
+}
+
+/// A [`Body`] taken apart for sending.
+pub(crate) enum Payload {
+    Bytes(Vec<u8>),
+    #[cfg(feature = "async")]
+    Stream(BodyStream),
+    #[cfg(feature = "blocking")]
+    Reader(Box<dyn Read + Send>),
+}
+
+impl Body {
+    pub fn empty() -> Self {
+        Self::default()
+    }
+
+    /// A body streamed from `stream`; `len`, when known, is sent as `Content-Length` and
+    /// must match the number of bytes the stream yields.
+    ///
+    /// Only the async transports can send it.
+    #[cfg(feature = "async")]
+    pub fn from_stream<S, B, E>(stream: S, len: Option<u64>) -> Self
+    where
+        S: Stream<Item = Result<B, E>> + Send + 'static,
+        B: Into<Bytes>,
+        E: Into<BoxError>,
+    {
+        Self(Kind::Stream {
+            stream: Arc::new(Mutex::new(Some(Box::pin(Chunks(Box::pin(stream)))))),
+            len,
+        })
+    }
+
+    /// A body read from `reader` until EOF; `len`, when known, is sent as
+    /// `Content-Length` and must match the number of bytes read.
+    ///
+    /// Only the blocking transport can send it.
+    #[cfg(feature = "blocking")]
+    pub fn from_reader(reader: impl Read + Send + 'static, len: Option<u64>) -> Self {
+        Self(Kind::Reader {
+            reader: Arc::new(Mutex::new(Some(Box::new(reader)))),
+            len,
+        })
+    }
+
+    /// The bytes of the body, unless it is streamed.
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match &self.0 {
+            Kind::Bytes(bytes) => Some(bytes),
+            #[cfg(feature = "async")]
+            Kind::Stream { .. } => None,
+            #[cfg(feature = "blocking")]
+            Kind::Reader { .. } => None,
+        }
+    }
+
+    /// Whether the body is known to be empty.
+    pub fn is_empty(&self) -> bool {
+        self.content_length() == Some(0)
+    }
+
+    /// Length of the body, if known.
+    pub fn content_length(&self) -> Option<u64> {
+        match &self.0 {
+            Kind::Bytes(bytes) => Some(bytes.len() as u64),
+            #[cfg(feature = "async")]
+            Kind::Stream { len, .. } => *len,
+            #[cfg(feature = "blocking")]
+            Kind::Reader { len, .. } => *len,
+        }
+    }
+
+    /// Whether the body can be sent more than once, i.e. it is not streamed.
+    pub fn is_replayable(&self) -> bool {
+        self.as_bytes().is_some()
+    }
+
+    /// Take the body as a stream, for a custom
+    /// [`AsyncTransport`](super::AsyncTransport); bytes are yielded in one chunk.
+    ///
+    /// Fails if a streaming body was already sent, or for a `from_reader` body.
+    #[cfg(feature = "async")]
+    pub fn into_stream(self) -> Result<BodyStream, BoxError> {
+        match self.into_payload()? {
+            Payload::Bytes(bytes) => Ok(buffered_stream(bytes)),
+            Payload::Stream(stream) => Ok(stream),
+            #[cfg(feature = "blocking")]
+            Payload::Reader(_) => Err("a reader body needs the blocking client".into()),
+        }
+    }
+
+    /// Take the body as a reader, for a custom
+    /// [`BlockingTransport`](super::BlockingTransport).
+    ///
+    /// Fails if a streaming body was already sent, or for a `from_stream` body.
+    #[cfg(feature = "blocking")]
+    pub fn into_reader(self) -> Result<BodyReader, BoxError> {
+        match self.into_payload()? {
+            Payload::Bytes(bytes) => Ok(Box::new(std::io::Cursor::new(bytes))),
+            Payload::Reader(reader) => Ok(reader),
+            #[cfg(feature = "async")]
+            Payload::Stream(_) => Err("a streaming body needs the async client".into()),
+        }
+    }
+
+    /// Take the body for sending; fails if a streaming body was already sent.
+    pub(crate) fn into_payload(self) -> Result<Payload, BoxError> {
+        match self.0 {
+            Kind::Bytes(bytes) => Ok(Payload::Bytes(bytes)),
+            #[cfg(feature = "async")]
+            Kind::Stream { stream, .. } => take(&stream).map(Payload::Stream),
+            #[cfg(feature = "blocking")]
+            Kind::Reader { reader, .. } => take(&reader).map(Payload::Reader),
+        }
+    }
+}
+
+impl From<Vec<u8>> for Body {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self(Kind::Bytes(bytes))
+    }
+}
+
+impl From<&[u8]> for Body {
+    fn from(bytes: &[u8]) -> Self {
+        Self::from(bytes.to_vec())
+    }
+}
+
+impl From<String> for Body {
+    fn from(text: String) -> Self {
+        Self::from(text.into_bytes())
+    }
+}
+
+impl From<&str> for Body {
+    fn from(text: &str) -> Self {
+        Self::from(text.as_bytes())
+    }
+}
+
+impl fmt::Debug for Body {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = if self.is_replayable() {
+            "bytes"
+        } else {
+            "stream"
+        };
+        f.debug_struct("Body")
+            .field("kind", &kind)
+            .field("len", &self.content_length())
+            .finish()
+    }
+}
+
+/// A [`BodyStream`] yielding `bytes` in one chunk.
+#[cfg(feature = "async")]
+pub(crate) fn buffered_stream(bytes: Vec<u8>) -> BodyStream {
+    Box::pin(Buffered(Some(Bytes::from(bytes))))
+}
+
+#[cfg(feature = "async")]
+struct Buffered(Option<Bytes>);
+
+#[cfg(feature = "async")]
+impl Stream for Buffered {
+    type Item = Result<Bytes, BoxError>;
+
+    fn poll_next(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Poll::Ready(self.get_mut().0.take().filter(|b| !b.is_empty()).map(Ok))
+    }
+}
+
+/// Read `stream` to the end.
+#[cfg(feature = "async")]
+pub(crate) async fn collect(mut stream: BodyStream) -> Result<Vec<u8>, BoxError> {
+    let mut body = Vec::new();
+    while let Some(chunk) = std::future::poll_fn(|cx| stream.as_mut().poll_next(cx)).await {
+        body.extend_from_slice(&chunk?);
+    }
+    Ok(body)
+}
+
+/// Adapts a caller's stream to [`BodyStream`] items.
+#[cfg(feature = "async")]
+struct Chunks<S>(Pin<Box<S>>);
+
+#[cfg(feature = "async")]
+impl<S, B, E> Stream for Chunks<S>
+where
+    S: Stream<Item = Result<B, E>>,
+    B: Into<Bytes>,
+    E: Into<BoxError>,
+{
+    type Item = Result<Bytes, BoxError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.0
+            .as_mut()
+            .poll_next(cx)
+            .map(|item| item.map(|chunk| chunk.map(Into::into).map_err(Into::into)))
+    }
+}
+
+/// A [`BodyStream`] that is `Sync`, as `reqwest` and the boxed `hyper` body require.
+#[cfg(feature = "async")]
+pub(crate) struct SyncStream(Mutex<BodyStream>);
+
+#[cfg(feature = "async")]
+impl SyncStream {
+    pub(crate) fn new(stream: BodyStream) -> Self {
+        Self(Mutex::new(stream))
+    }
+}
+
+#[cfg(feature = "async")]
+impl Stream for SyncStream {
+    type Item = Result<hyper::body::Frame<Bytes>, BoxError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut()
+            .0
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_mut()
+            .poll_next(cx)
+            .map(|item| item.map(|chunk| chunk.map(hyper::body::Frame::data)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bytes_body_is_replayable() {
+        let body = Body::from("hello");
+        assert_eq!(body.as_bytes(), Some(&b"hello"[..]));
+        assert_eq!(body.content_length(), Some(5));
+        assert!(body.is_replayable());
+        assert!(Body::empty().is_empty());
+        assert!(matches!(
+            body.clone().into_payload(),
+            Ok(Payload::Bytes(bytes)) if bytes == b"hello"
+        ));
+        assert!(body.into_payload().is_ok());
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test(flavor = "current_thread")]
+    async fn buffered_stream_collects_back_to_its_bytes() {
+        assert_eq!(
+            collect(buffered_stream(b"abc".to_vec())).await.unwrap(),
+            b"abc"
+        );
+        assert!(
+            collect(buffered_stream(Vec::new()))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn streaming_body_is_sent_once() {
+        let body = Body::from_reader(std::io::Cursor::new(b"abc".to_vec()), None);
+        let clone = body.clone();
+        assert!(!body.is_replayable());
+        assert_eq!(body.content_length(), None);
+        assert!(!body.is_empty());
+        assert_eq!(format!("{body:?}"), r#"Body { kind: "stream", len: None }"#);
+
+        let Ok(Payload::Reader(mut reader)) = body.into_payload() else {
+            panic!("expected a reader");
+        };
+        let mut read = String::new();
+        reader.read_to_string(&mut read).unwrap();
+        assert_eq!(read, "abc");
+
+        let err = clone.into_payload().err().unwrap();
+        assert_eq!(err.to_string(), "streaming request body was already sent");
+    }
+}
@@ -1,11 +1,21 @@
-use std::time::Duration;
+//! HTTP transports used by the clients, and the traits for plugging in a custom one.
+
+use std::{fmt, time::Duration};
+
+#[cfg(any(feature = "async", feature = "blocking"))]
+use std::sync::Arc;
 
 #[cfg(feature = "async")]
 use std::{future::Future, pin::Pin};
 
-use http::{HeaderMap, Method, StatusCode};
+use http::{HeaderMap, HeaderValue, Method, StatusCode, header};
+
+use crate::error::Error;
 
+mod body;
+mod proxy;
 pub(crate) mod retry;
+mod tls;
 
 #[cfg(feature = "async")]
 pub(crate) mod async_transport;
@@ -13,32 +23,238 @@ pub(crate) mod async_transport;
 #[cfg(feature = "blocking")]
 pub(crate) mod blocking_transport;
 
-pub(crate) type BoxError = Box<dyn std::error::Error + Send + Sync + 'static>;
+pub use body::Body;
+#[cfg(feature = "blocking")]
+pub use body::BodyReader;
+#[cfg(feature = "async")]
+pub use body::BodyStream;
+pub(crate) use body::Payload;
+#[cfg(feature = "async")]
+pub(crate) use body::{SyncStream, buffered_stream, collect};
+pub use proxy::Proxy;
+#[cfg(any(feature = "async", feature = "blocking"))]
+pub(crate) use tls::TlsConfig;
+pub use tls::{Certificate, Identity};
+
+/// Error returned by a transport; surfaced as [`Error::Transport`].
+pub type BoxError = Box<dyn std::error::Error + Send + Sync + 'static>;
 
+/// A signed HTTP request, ready to be sent as is.
+///
+/// Built by the clients and handed to the transport; new fields may be added.
 #[derive(Debug, Clone)]
-pub(crate) struct Request {
+#[non_exhaustive]
+pub struct Request {
     pub method: Method,
     pub url: url::Url,
+    /// Headers, including the signature; sensitive values are marked as such.
     pub headers: HeaderMap,
+    pub body: Body,
+    /// Deadline for the whole exchange, or only until the response headers arrive when
+    /// the response is streamed; the transport must enforce it.
     pub timeout: Duration,
 }
 
+impl Request {
+    /// Add `Content-Length` for a streaming body of known length, which would otherwise
+    /// be sent chunked.
+    pub(crate) fn set_content_length(&mut self) {
+        if !self.body.is_replayable()
+            && let Some(len) = self.body.content_length()
+        {
+            self.headers
+                .entry(header::CONTENT_LENGTH)
+                .or_insert(HeaderValue::from(len));
+        }
+    }
+}
+
+/// An HTTP response with its body read to the end.
 #[derive(Debug, Clone)]
-pub(crate) struct Response {
+pub struct Response {
     pub status: StatusCode,
     pub headers: HeaderMap,
     pub body: Vec<u8>,
 }
 
+/// An HTTP response whose body is read as it arrives: a `BodyStream` for the async
+/// transports, a `BodyReader` for the blocking one.
+pub struct StreamingResponse<B> {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: B,
+}
+
+impl<B> fmt::Debug for StreamingResponse<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StreamingResponse")
+            .field("status", &self.status)
+            .field("headers", &self.headers)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Boxed future returned by [`AsyncTransport::send`].
 #[cfg(feature = "async")]
-pub(crate) trait AsyncTransport: Send + Sync {
-    fn send<'a>(
-        &'a self,
-        request: Request,
-    ) -> Pin<Box<dyn Future<Output = Result<Response, BoxError>> + Send + 'a>>;
+pub type TransportFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<Response, BoxError>> + Send + 'a>>;
+
+/// Boxed future returned by [`AsyncTransport::send_streaming`].
+#[cfg(feature = "async")]
+pub type StreamingTransportFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<StreamingResponse<BodyStream>, BoxError>> + Send + 'a>>;
+
+/// HTTP stack of [`Client`](crate::Client), set with
+/// [`ClientBuilder::transport`](crate::client::ClientBuilder::transport).
+///
+/// Non-success statuses are returned as responses, not errors. Errors are retried when
+/// they are timeouts or connection failures: a `reqwest` timeout or connect error,
+/// `tokio::time::error::Elapsed`, or an `std::io::Error` of such a kind in the source
+/// chain.
+#[cfg(feature = "async")]
+pub trait AsyncTransport: Send + Sync {
+    fn send(&self, request: Request) -> TransportFuture<'_>;
+
+    /// Send `request` and return once the response headers arrive, leaving the body to
+    /// be streamed by the caller.
+    ///
+    /// The default implementation reads the whole body with [`send`](Self::send).
+    fn send_streaming(&self, request: Request) -> StreamingTransportFuture<'_> {
+        Box::pin(async move {
+            let response = self.send(request).await?;
+            Ok(StreamingResponse {
+                status: response.status,
+                headers: response.headers,
+                body: buffered_stream(response.body),
+            })
+        })
+    }
 }
 
+/// HTTP stack of [`BlockingClient`](crate::BlockingClient), set with
+/// [`BlockingClientBuilder::transport`](crate::client::BlockingClientBuilder::transport).
+///
+/// Non-success statuses are returned as responses, not errors; errors are retried up to
+/// the client's retry limit.
 #[cfg(feature = "blocking")]
-pub(crate) trait BlockingTransport: Send + Sync {
+pub trait BlockingTransport: Send + Sync {
     fn send(&self, request: Request) -> Result<Response, BoxError>;
+
+    /// Send `request` and return once the response headers arrive, leaving the body to
+    /// be read by the caller.
+    ///
+    /// The default implementation reads the whole body with [`send`](Self::send).
+    fn send_streaming(&self, request: Request) -> Result<StreamingResponse<BodyReader>, BoxError> {
+        let response = self.send(request)?;
+        Ok(StreamingResponse {
+            status: response.status,
+            headers: response.headers,
+            body: Box::new(std::io::Cursor::new(response.body)),
+        })
+    }
+}
+
+/// Connection settings of the built-in transports; unset values keep the HTTP
+/// library's defaults.
+#[cfg(any(feature = "async", feature = "blocking"))]
+#[derive(Debug, Clone)]
+pub(crate) struct ConnectionConfig {
+    pub(crate) connect_timeout: Duration,
+    /// `ureq` only; the async transports only limit idle connections per host.
+    #[cfg_attr(not(feature = "blocking"), allow(dead_code))]
+    pub(crate) pool_max_idle: Option<usize>,
+    pub(crate) pool_max_idle_per_host: Option<usize>,
+    pub(crate) pool_idle_timeout: Option<Duration>,
+    /// Async transports only: `ureq` does not set socket options.
+    #[cfg_attr(not(feature = "async"), allow(dead_code))]
+    pub(crate) tcp_keepalive: Option<Duration>,
+    /// Async transports only: offer HTTP/2 during the TLS handshake.
+    #[cfg_attr(not(feature = "async"), allow(dead_code))]
+    pub(crate) http2: bool,
+    pub(crate) proxy: ProxyConfig,
+    pub(crate) tls: TlsConfig,
+}
+
+#[cfg(any(feature = "async", feature = "blocking"))]
+impl Default for ConnectionConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(10),
+            pool_max_idle: None,
+            pool_max_idle_per_host: None,
+            pool_idle_timeout: None,
+            tcp_keepalive: None,
+            http2: false,
+            proxy: ProxyConfig::Env,
+            tls: TlsConfig::default(),
+        }
+    }
+}
+
+/// Where the built-in transports take their proxy from.
+#[cfg(any(feature = "async", feature = "blocking"))]
+#[derive(Debug, Clone)]
+pub(crate) enum ProxyConfig {
+    /// [`Proxy::from_env`], read when the transport is built.
+    Env,
+    Custom(Proxy),
+    /// Connect directly, ignoring the proxy environment variables.
+    Direct,
+}
+
+#[cfg(any(feature = "async", feature = "blocking"))]
+impl ConnectionConfig {
+    fn proxy(&self) -> Result<Option<Proxy>, Error> {
+        match &self.proxy {
+            ProxyConfig::Env => Proxy::from_env(),
+            ProxyConfig::Custom(proxy) => Ok(Some(proxy.clone())),
+            ProxyConfig::Direct => Ok(None),
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+pub(crate) fn default_async_transport(
+    config: &ConnectionConfig,
+) -> Result<Arc<dyn AsyncTransport>, Error> {
+    let proxy = config.proxy()?;
+
+    #[cfg(feature = "native-tls")]
+    {
+        return Ok(Arc::new(
+            async_transport::ReqwestTransport::new(config, proxy.as_ref()).map_err(|e| {
+                Error::invalid_config("failed to build async http transport (reqwest)", Some(e))
+            })?,
+        ));
+    }
+
+    #[cfg(feature = "rustls")]
+    {
+        return Ok(Arc::new(
+            async_transport::HyperRustlsTransport::new(config, proxy.as_ref()).map_err(|e| {
+                Error::invalid_config(
+                    "failed to build async http transport (hyper-rustls)",
+                    Some(e),
+                )
+            })?,
+        ));
+    }
+
+    #[allow(unreachable_code)]
+    Err(Error::invalid_config(
+        "no async http transport available",
+        None,
+    ))
+}
+
+#[cfg(feature = "blocking")]
+pub(crate) fn default_blocking_transport(
+    config: &ConnectionConfig,
+) -> Result<Arc<dyn BlockingTransport>, Error> {
+    let proxy = config.proxy()?;
+    Ok(Arc::new(
+        blocking_transport::UreqTransport::new(config, proxy.as_ref()).map_err(|e| {
+            Error::invalid_config("failed to build blocking http transport", Some(e))
+        })?,
+    ))
 }
@@ -2,6 +2,7 @@
 
 pub mod billing;
 pub mod ecs;
+pub mod roa;
 pub mod sts;
 
 mod common;
@@ -0,0 +1,107 @@
+use std::{collections::BTreeMap, fmt};
+
+use http::{HeaderMap, HeaderName, HeaderValue, Method, header};
+use serde::Serialize;
+
+use crate::{error::Error, transport::Body};
+
+/// A call to a ROA (RESTful) API, such as Container Service or Function Compute.
+///
+/// Sent with `Client::roa_json` / `BlockingClient::roa_json`, which sign it with the
+/// client's credentials.
+///
+/// ```
+/// use alibabacloud::types::roa::RoaRequest;
+/// use http::Method;
+///
+/// let request = RoaRequest::new(
+///     Method::POST,
+///     "https://cs.cn-hangzhou.aliyuncs.com/",
+///     "2015-12-15",
+///     "/clusters",
+/// )
+/// .action("CreateCluster")
+/// .json(&serde_json::json!({"name": "demo"}))?;
+/// # Ok::<(), alibabacloud::Error>(())
+/// ```
+#[derive(Clone)]
+pub struct RoaRequest {
+    pub(crate) method: Method,
+    pub(crate) endpoint: String,
+    pub(crate) version: String,
+    pub(crate) path: String,
+    pub(crate) action: Option<String>,
+    pub(crate) query: BTreeMap<String, String>,
+    pub(crate) headers: HeaderMap,
+    pub(crate) body: Body,
+}
+
+impl RoaRequest {
+    /// `path` is relative to `endpoint` (e.g. `/clusters/{id}`); its segments are
+    /// percent-encoded.
+    pub fn new(
+        method: Method,
+        endpoint: impl Into<String>,
+        version: impl Into<String>,
+        path: impl Into<String>,
+    ) -> Self {
+        Self {
+            method,
+            endpoint: endpoint.into(),
+            version: version.into(),
+            path: path.into(),
+            action: None,
+            query: BTreeMap::new(),
+            headers: HeaderMap::new(),
+            body: Body::empty(),
+        }
+    }
+
+    /// API name, sent as `x-acs-action` (required with signature V3).
+    pub fn action(mut self, action: impl Into<String>) -> Self {
+        self.action = Some(action.into());
+        self
+    }
+
+    pub fn query(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.query.insert(key.into(), value.into());
+        self
+    }
+
+    pub fn header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+
+    /// Send `body` as JSON.
+    pub fn json<B: Serialize + ?Sized>(self, body: &B) -> Result<Self, Error> {
+        let body = serde_json::to_vec(body).map_err(|e| {
+            Error::invalid_config("failed to serialize request body", Some(Box::new(e)))
+        })?;
+        Ok(self.body(HeaderValue::from_static("application/json"), body))
+    }
+
+    /// Send `body` with the given `Content-Type`.
+    ///
+    /// A streaming [`Body`] can only be signed with signature 1.0, and a request carrying
+    /// one is not retried.
+    pub fn body(mut self, content_type: HeaderValue, body: impl Into<Body>) -> Self {
+        self.headers.insert(header::CONTENT_TYPE, content_type);
+        self.body = body.into();
+        self
+    }
+}
+
+impl fmt::Debug for RoaRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RoaRequest")
+            .field("method", &self.method)
+            .field("endpoint", &self.endpoint)
+            .field("version", &self.version)
+            .field("path", &self.path)
+            .field("action", &self.action)
+            .field("query", &self.query.keys().collect::<Vec<_>>())
+            .field("body", &self.body)
+            .finish()
+    }
+}
@@ -1,5 +1,12 @@
+use std::{collections::BTreeMap, fmt};
+
 use serde::{Deserialize, Serialize};
 
+use crate::{
+    auth::{AccessKey, SecretString, parse_expiration},
+    error::Error,
+};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum IdentityType {
     Account,
@@ -19,3 +26,213 @@ pub struct CallerIdentity {
     pub arn: String,
     pub role_id: Option<String>,
 }
+
+/// Parameters of STS `AssumeRole`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AssumeRoleParams {
+    pub role_arn: String,
+    pub role_session_name: String,
+    /// Inline policy that further restricts the session.
+    pub policy: Option<String>,
+    /// Session lifetime, 900 seconds up to the role's maximum (3600 by default).
+    pub duration_seconds: Option<u32>,
+    pub external_id: Option<String>,
+}
+
+impl AssumeRoleParams {
+    pub(crate) fn into_query(self) -> BTreeMap<String, String> {
+        let mut map = BTreeMap::new();
+        map.insert("RoleArn".to_owned(), self.role_arn);
+        map.insert("RoleSessionName".to_owned(), self.role_session_name);
+        if let Some(policy) = self.policy {
+            map.insert("Policy".to_owned(), policy);
+        }
+        if let Some(duration_seconds) = self.duration_seconds {
+            map.insert("DurationSeconds".to_owned(), duration_seconds.to_string());
+        }
+        if let Some(external_id) = self.external_id {
+            map.insert("ExternalId".to_owned(), external_id);
+        }
+        map
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct AssumeRoleResponse {
+    pub request_id: String,
+    pub credentials: StsCredentials,
+    pub assumed_role_user: AssumedRoleUser,
+}
+
+/// Temporary credentials issued by STS.
+///
+/// The secret and the security token are only reachable through their accessors and are
+/// redacted in `Debug` output.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct StsCredentials {
+    pub access_key_id: String,
+    access_key_secret: SecretString,
+    security_token: SecretString,
+    /// RFC 3339 timestamp, e.g. `2015-04-09T11:52:19Z`.
+    pub expiration: String,
+}
+
+impl StsCredentials {
+    pub fn access_key_secret(&self) -> &SecretString {
+        &self.access_key_secret
+    }
+
+    pub fn security_token(&self) -> &SecretString {
+        &self.security_token
+    }
+}
+
+impl TryFrom<StsCredentials> for crate::auth::Credentials {
+    type Error = Error;
+
+    fn try_from(credentials: StsCredentials) -> Result<Self, Error> {
+        let expiration = parse_expiration(&credentials.expiration)?;
+        let mut access_key =
+            AccessKey::from_secret(credentials.access_key_id, credentials.access_key_secret);
+        access_key.security_token = Some(credentials.security_token);
+        Ok(crate::auth::Credentials::from(access_key).with_expiration(expiration))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct AssumedRoleUser {
+    pub arn: String,
+    pub assumed_role_id: String,
+}
+
+/// Parameters of STS `AssumeRoleWithOIDC`.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct AssumeRoleWithOidcParams {
+    pub role_arn: String,
+    pub oidc_provider_arn: String,
+    /// The OIDC token (JWT) issued by the identity provider.
+    pub oidc_token: String,
+    pub role_session_name: Option<String>,
+    pub policy: Option<String>,
+    pub duration_seconds: Option<u32>,
+}
+
+impl AssumeRoleWithOidcParams {
+    pub(crate) fn into_query(self) -> BTreeMap<String, String> {
+        let mut map = BTreeMap::new();
+        map.insert("RoleArn".to_owned(), self.role_arn);
+        map.insert("OIDCProviderArn".to_owned(), self.oidc_provider_arn);
+        map.insert("OIDCToken".to_owned(), self.oidc_token);
+        if let Some(role_session_name) = self.role_session_name {
+            map.insert("RoleSessionName".to_owned(), role_session_name);
+        }
+        if let Some(policy) = self.policy {
+            map.insert("Policy".to_owned(), policy);
+        }
+        if let Some(duration_seconds) = self.duration_seconds {
+            map.insert("DurationSeconds".to_owned(), duration_seconds.to_string());
+        }
+        map
+    }
+}
+
+impl fmt::Debug for AssumeRoleWithOidcParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AssumeRoleWithOidcParams")
+            .field("role_arn", &self.role_arn)
+            .field("oidc_provider_arn", &self.oidc_provider_arn)
+            .field("oidc_token", &"<redacted>")
+            .field("role_session_name", &self.role_session_name)
+            .field("policy", &self.policy)
+            .field("duration_seconds", &self.duration_seconds)
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct AssumeRoleWithOidcResponse {
+    pub request_id: String,
+    pub credentials: StsCredentials,
+    pub assumed_role_user: AssumedRoleUser,
+    #[serde(rename = "OIDCTokenInfo", default)]
+    pub oidc_token_info: Option<OidcTokenInfo>,
+}
+
+/// Claims of the OIDC token that STS accepted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct OidcTokenInfo {
+    #[serde(default)]
+    pub subject: Option<String>,
+    #[serde(default)]
+    pub issuer: Option<String>,
+    #[serde(default)]
+    pub client_ids: Option<String>,
+}
+
+/// Parameters of STS `AssumeRoleWithSAML`.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct AssumeRoleWithSamlParams {
+    pub saml_provider_arn: String,
+    pub role_arn: String,
+    /// Base64-encoded SAML response from the IdP.
+    pub saml_assertion: String,
+    pub policy: Option<String>,
+    pub duration_seconds: Option<u32>,
+}
+
+impl AssumeRoleWithSamlParams {
+    pub(crate) fn into_query(self) -> BTreeMap<String, String> {
+        let mut map = BTreeMap::new();
+        map.insert("SAMLProviderArn".to_owned(), self.saml_provider_arn);
+        map.insert("RoleArn".to_owned(), self.role_arn);
+        map.insert("SAMLAssertion".to_owned(), self.saml_assertion);
+        if let Some(policy) = self.policy {
+            map.insert("Policy".to_owned(), policy);
+        }
+        if let Some(duration_seconds) = self.duration_seconds {
+            map.insert("DurationSeconds".to_owned(), duration_seconds.to_string());
+        }
+        map
+    }
+}
+
+impl fmt::Debug for AssumeRoleWithSamlParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AssumeRoleWithSamlParams")
+            .field("saml_provider_arn", &self.saml_provider_arn)
+            .field("role_arn", &self.role_arn)
+            .field("saml_assertion", &"<redacted>")
+            .field("policy", &self.policy)
+            .field("duration_seconds", &self.duration_seconds)
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct AssumeRoleWithSamlResponse {
+    pub request_id: String,
+    pub credentials: StsCredentials,
+    pub assumed_role_user: AssumedRoleUser,
+    #[serde(rename = "SAMLAssertionInfo", default)]
+    pub saml_assertion_info: Option<SamlAssertionInfo>,
+}
+
+/// Details of the SAML assertion that STS accepted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct SamlAssertionInfo {
+    #[serde(default)]
+    pub subject_type: Option<String>,
+    #[serde(default)]
+    pub subject: Option<String>,
+    #[serde(default)]
+    pub recipient: Option<String>,
+    #[serde(default)]
+    pub issuer: Option<String>,
+}
@@ -1,3 +1,5 @@
+pub(crate) mod acs3;
 pub(crate) mod redact;
+pub(crate) mod roa;
 pub(crate) mod rpc;
 pub(crate) mod url;
@@ -1,11 +1,13 @@
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, time::SystemTime};
 
 use base64::{Engine as _, engine::general_purpose};
 use hmac::{Hmac, Mac};
+use http::{HeaderMap, HeaderName, HeaderValue, Method, header};
 use sha1::Sha1;
 
-use crate::auth::AccessKey;
+use crate::auth::{AccessKey, SignatureAlgorithm};
 use crate::error::Error;
+use crate::util::{acs3, url as url_util};
 
 pub(crate) type HmacSha1 = Hmac<Sha1>;
 
@@ -46,8 +48,9 @@ pub(crate) fn signature(
     Ok(general_purpose::STANDARD.encode(mac.finalize().into_bytes()))
 }
 
-pub(crate) fn timestamp() -> Result<String, Error> {
-    let now = time::OffsetDateTime::now_utc()
+/// `now` as an ISO 8601 UTC timestamp with seconds precision.
+pub(crate) fn timestamp(now: SystemTime) -> Result<String, Error> {
+    let now = time::OffsetDateTime::from(now)
         .replace_nanosecond(0)
         .map_err(|e| Error::invalid_config("failed to normalize timestamp", Some(Box::new(e))))?;
     now.format(&time::format_description::well_known::Rfc3339)
@@ -57,6 +60,7 @@ pub(crate) fn timestamp() -> Result<String, Error> {
 pub(crate) fn inject_common_rpc_params(
     params: &mut BTreeMap<String, String>,
     access_key: &AccessKey,
+    now: SystemTime,
 ) -> Result<(), Error> {
     params.insert("AccessKeyId".to_owned(), access_key.access_key_id.clone());
     params.insert("SignatureMethod".to_owned(), "HMAC-SHA1".to_owned());
@@ -65,15 +69,126 @@ pub(crate) fn inject_common_rpc_params(
         "SignatureNonce".to_owned(),
         uuid::Uuid::new_v4().to_string(),
     );
-    params.insert("Timestamp".to_owned(), timestamp()?);
+    params.insert("Timestamp".to_owned(), timestamp(now)?);
     if let Some(token) = access_key.security_token.as_ref() {
-        params.insert("SecurityToken".to_owned(), token.expose().to_owned());
+        params.insert("SecurityToken".to_owned(), token.expose_secret().to_owned());
     }
     Ok(())
 }
 
+/// Parameters longer than this (once encoded) are sent in a form body rather than the
+/// query string, which gateways and proxies commonly cap at a few KiB.
+pub(crate) const MAX_QUERY_LEN: usize = 4096;
+
+/// A signed RPC call.
+#[derive(Debug)]
+pub(crate) struct RpcCall {
+    pub(crate) method: Method,
+    pub(crate) url: url::Url,
+    pub(crate) headers: HeaderMap,
+    pub(crate) body: Vec<u8>,
+}
+
+/// Build an RPC call: a `GET` with the parameters in the query string or, with
+/// `form_body` or parameters longer than [`MAX_QUERY_LEN`], a `POST` with them in an
+/// `application/x-www-form-urlencoded` body. Either way the signature covers the method.
+///
+/// Without an access key the call is sent unsigned (e.g. STS `AssumeRoleWithOIDC`).
+/// `now` is the signing time.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn request(
+    base_url: &url::Url,
+    action: &str,
+    version: &str,
+    mut params: BTreeMap<String, String>,
+    access_key: Option<&AccessKey>,
+    algorithm: SignatureAlgorithm,
+    mut headers: HeaderMap,
+    now: SystemTime,
+    form_body: bool,
+) -> Result<RpcCall, Error> {
+    let mut url = url_util::endpoint(base_url, &[])?;
+    let method = if form_body || canonical_query(&params).len() > MAX_QUERY_LEN {
+        Method::POST
+    } else {
+        Method::GET
+    };
+
+    let body = match (access_key, acs3::Algorithm::from_signature(algorithm)) {
+        (Some(access_key), Some(algorithm)) => {
+            for (name, value) in [("x-acs-action", action), ("x-acs-version", version)] {
+                let value = HeaderValue::from_str(value).map_err(|e| {
+                    Error::invalid_config(format!("invalid {name}"), Some(Box::new(e)))
+                })?;
+                headers.insert(HeaderName::from_static(name), value);
+            }
+            let body = encode(&method, &mut url, &mut headers, &params);
+            acs3::sign(
+                algorithm,
+                &method,
+                &url,
+                &mut headers,
+                &body,
+                access_key,
+                now,
+            )?;
+            body
+        }
+        (access_key, _) => {
+            params.insert("Action".to_owned(), action.to_owned());
+            params.insert("Version".to_owned(), version.to_owned());
+            params
+                .entry("Format".to_owned())
+                .or_insert("JSON".to_owned());
+
+            if let Some(access_key) = access_key {
+                inject_common_rpc_params(&mut params, access_key, now)?;
+
+                let canonical_query = canonical_query(&params);
+                let signature = signature(
+                    &method,
+                    &canonical_query,
+                    access_key.access_key_secret.expose_secret(),
+                )?;
+                params.insert("Signature".to_owned(), signature);
+            }
+            encode(&method, &mut url, &mut headers, &params)
+        }
+    };
+
+    Ok(RpcCall {
+        method,
+        url,
+        headers,
+        body,
+    })
+}
+
+/// Put `params` in the query string of a `GET`, or return them as the form body of a
+/// `POST`.
+fn encode(
+    method: &Method,
+    url: &mut url::Url,
+    headers: &mut HeaderMap,
+    params: &BTreeMap<String, String>,
+) -> Vec<u8> {
+    let encoded = canonical_query(params);
+    if method == Method::POST {
+        headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/x-www-form-urlencoded"),
+        );
+        encoded.into_bytes()
+    } else {
+        url.set_query((!encoded.is_empty()).then_some(encoded.as_str()));
+        Vec::new()
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use sha2::Digest as _;
+
     use super::*;
 
     #[test]
@@ -117,9 +232,71 @@ mod tests {
         assert_eq!(sig, "D93NxUhhlH206jRKH5QQOSAUcT4=");
     }
 
+    fn call(
+        params: BTreeMap<String, String>,
+        algorithm: SignatureAlgorithm,
+        form_body: bool,
+    ) -> RpcCall {
+        request(
+            &url::Url::parse("https://ecs.example.com/").unwrap(),
+            "RunInstances",
+            "2014-05-26",
+            params,
+            Some(&AccessKey::new("id", "secret")),
+            algorithm,
+            HeaderMap::new(),
+            SystemTime::now(),
+            form_body,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn long_params_are_signed_as_post_form() {
+        let short = BTreeMap::from([("UserData".to_owned(), "x".repeat(16))]);
+        let get = call(short.clone(), SignatureAlgorithm::HmacSha1, false);
+        assert_eq!(get.method, Method::GET);
+        assert!(get.body.is_empty());
+        assert!(get.url.query().unwrap().contains("UserData="));
+
+        let long = BTreeMap::from([("UserData".to_owned(), "x".repeat(MAX_QUERY_LEN))]);
+        for (params, form_body) in [(short, true), (long, false)] {
+            let post = call(params.clone(), SignatureAlgorithm::HmacSha1, form_body);
+            assert_eq!(post.method, Method::POST);
+            assert_eq!(post.url.query(), None);
+            assert_eq!(
+                post.headers[header::CONTENT_TYPE],
+                "application/x-www-form-urlencoded"
+            );
+
+            let mut sent: BTreeMap<String, String> = url::form_urlencoded::parse(&post.body)
+                .into_owned()
+                .collect();
+            assert_eq!(sent["UserData"], params["UserData"]);
+            let signature_sent = sent.remove("Signature").unwrap();
+            assert_eq!(
+                signature(&Method::POST, &canonical_query(&sent), "secret").unwrap(),
+                signature_sent
+            );
+        }
+    }
+
+    #[test]
+    fn v3_post_form_hashes_the_body() {
+        let params = BTreeMap::from([("UserData".to_owned(), "a b".to_owned())]);
+        let post = call(params, SignatureAlgorithm::Acs3HmacSha256, true);
+        assert_eq!(post.method, Method::POST);
+        assert_eq!(post.body, b"UserData=a%20b");
+        assert_eq!(post.url.query(), None);
+        assert_eq!(
+            post.headers["x-acs-content-sha256"],
+            acs3::hex(&sha2::Sha256::digest(&post.body)).as_str()
+        );
+    }
+
     #[test]
     fn timestamp_is_seconds_precision_utc() {
-        let ts = timestamp().unwrap();
+        let ts = timestamp(SystemTime::now()).unwrap();
         assert!(ts.ends_with('Z'));
         assert!(!ts.contains('.'));
     }
@@ -0,0 +1,334 @@
+//! Fixtures shared by the async and blocking integration tests.
+#![allow(dead_code)]
+
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+use alibabacloud::types::sts::{AssumeRoleParams, AssumeRoleWithSamlParams};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, pem::PemObject};
+use wiremock::{
+    Mock, MockServer, ResponseTemplate,
+    matchers::{header, method, path, query_param},
+};
+
+pub const STS_IDENTITY_BODY: &str = r#"{"IdentityType":"Account","RequestId":"req","AccountId":"1","PrincipalId":"p","UserId":"u","Arn":"arn","RoleId":null}"#;
+
+pub async fn mount_ecs_metadata(server: &MockServer) {
+    Mock::given(method("PUT"))
+        .and(path("/latest/api/token"))
+        .and(header("x-aliyun-ecs-metadata-token-ttl-seconds", "21600"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("imds-token"))
+        .mount(server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/latest/meta-data/ram/security-credentials/"))
+        .and(header("x-aliyun-ecs-metadata-token", "imds-token"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("ecs-role\n"))
+        .mount(server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/latest/meta-data/ram/security-credentials/ecs-role"))
+        .and(header("x-aliyun-ecs-metadata-token", "imds-token"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            r#"{"Code":"Success","AccessKeyId":"STS.ecs","AccessKeySecret":"ecs-secret","SecurityToken":"ecs-token","Expiration":"2099-01-01T00:00:00Z","LastUpdated":"2024-01-01T00:00:00Z"}"#,
+            "application/json",
+        ))
+        .mount(server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .and(query_param("Action", "GetCallerIdentity"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_raw(STS_IDENTITY_BODY, "application/json"),
+        )
+        .mount(server)
+        .await;
+}
+
+pub const ASSUME_ROLE_BODY: &str = r#"{"RequestId":"assume-req","Credentials":{"AccessKeyId":"STS.role","AccessKeySecret":"role-secret","SecurityToken":"role-token","Expiration":"2099-01-01T00:00:00Z"},"AssumedRoleUser":{"Arn":"acs:ram::1:role/test/session","AssumedRoleId":"3:session"}}"#;
+
+pub async fn mount_assume_role(server: &MockServer) {
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .and(query_param("Action", "AssumeRole"))
+        .and(query_param("RoleArn", "acs:ram::1:role/test"))
+        .and(query_param("DurationSeconds", "900"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(ASSUME_ROLE_BODY, "application/json"))
+        .mount(server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .and(query_param("Action", "GetCallerIdentity"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_raw(STS_IDENTITY_BODY, "application/json"),
+        )
+        .mount(server)
+        .await;
+}
+
+pub fn assume_role_params() -> AssumeRoleParams {
+    AssumeRoleParams {
+        role_arn: "acs:ram::1:role/test".to_owned(),
+        role_session_name: "session".to_owned(),
+        duration_seconds: Some(900),
+        ..Default::default()
+    }
+}
+
+pub fn count_actions(requests: &[wiremock::Request], action: &str) -> usize {
+    let needle = format!("Action={action}");
+    requests
+        .iter()
+        .filter(|request| request.url.query().unwrap_or_default().contains(&needle))
+        .count()
+}
+
+pub async fn mount_role(server: &MockServer, role_arn: &str, access_key_id: &str) {
+    let body = format!(
+        r#"{{"RequestId":"assume-req","Credentials":{{"AccessKeyId":"{access_key_id}","AccessKeySecret":"secret","SecurityToken":"token","Expiration":"2099-01-01T00:00:00Z"}},"AssumedRoleUser":{{"Arn":"{role_arn}/session","AssumedRoleId":"3:session"}}}}"#
+    );
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .and(query_param("Action", "AssumeRole"))
+        .and(query_param("RoleArn", role_arn))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(body, "application/json"))
+        .mount(server)
+        .await;
+}
+
+pub fn role(role_arn: &str, role_session_name: &str) -> AssumeRoleParams {
+    AssumeRoleParams {
+        role_arn: role_arn.to_owned(),
+        role_session_name: role_session_name.to_owned(),
+        ..Default::default()
+    }
+}
+
+pub fn requests_for_role<'a>(
+    requests: &'a [wiremock::Request],
+    role_arn: &str,
+) -> Vec<&'a wiremock::Request> {
+    let needle = format!(
+        "RoleArn={}",
+        role_arn.replace(':', "%3A").replace('/', "%2F")
+    );
+    requests
+        .iter()
+        .filter(|request| request.url.query().unwrap_or_default().contains(&needle))
+        .collect()
+}
+
+pub const OIDC_BODY: &str = r#"{"RequestId":"oidc-req","Credentials":{"AccessKeyId":"STS.oidc","AccessKeySecret":"oidc-secret","SecurityToken":"oidc-token","Expiration":"2000-01-01T00:00:00Z"},"AssumedRoleUser":{"Arn":"acs:ram::1:role/pod/session","AssumedRoleId":"4:session"},"OIDCTokenInfo":{"Subject":"system:serviceaccount:default:app","Issuer":"https://oidc.example","ClientIds":"sts.aliyuncs.com"}}"#;
+
+pub async fn mount_assume_role_with_oidc(server: &MockServer) {
+    Mock::given(method("POST"))
+        .and(path("/"))
+        .and(header("content-type", "application/x-www-form-urlencoded"))
+        .and(form_param("Action", "AssumeRoleWithOIDC"))
+        .and(form_param("RoleArn", "acs:ram::1:role/pod"))
+        .and(form_param(
+            "OIDCProviderArn",
+            "acs:ram::1:oidc-provider/ack",
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(OIDC_BODY, "application/json"))
+        .mount(server)
+        .await;
+}
+
+/// Matches a request whose `application/x-www-form-urlencoded` body has `key=value`.
+pub struct FormParam(&'static str, String);
+
+pub fn form_param(key: &'static str, value: impl Into<String>) -> FormParam {
+    FormParam(key, value.into())
+}
+
+impl wiremock::Match for FormParam {
+    fn matches(&self, request: &wiremock::Request) -> bool {
+        url::form_urlencoded::parse(&request.body).any(|(k, v)| k == self.0 && v == *self.1)
+    }
+}
+
+pub const SAML_BODY: &str = r#"{"RequestId":"saml-req","Credentials":{"AccessKeyId":"STS.saml","AccessKeySecret":"saml-secret","SecurityToken":"saml-token","Expiration":"2099-01-01T00:00:00Z"},"AssumedRoleUser":{"Arn":"acs:ram::1:role/sso/alice","AssumedRoleId":"5:alice"},"SAMLAssertionInfo":{"SubjectType":"persistent","Subject":"alice","Recipient":"https://signin.aliyun.com/saml-role/sso","Issuer":"https://idp.example"}}"#;
+
+pub fn saml_params(assertion: &str) -> AssumeRoleWithSamlParams {
+    AssumeRoleWithSamlParams {
+        saml_provider_arn: "acs:ram::1:saml-provider/idp".to_owned(),
+        role_arn: "acs:ram::1:role/sso".to_owned(),
+        saml_assertion: assertion.to_owned(),
+        duration_seconds: Some(3600),
+        ..Default::default()
+    }
+}
+
+/// A base64 assertion too large for a query string, so it is sent in a form body.
+pub fn large_assertion() -> String {
+    "PHNhbWxwOlJlc3BvbnNl+/=".repeat(400)
+}
+
+pub const TLS_CERT: &[u8] = include_bytes!("../fixtures/tls-cert.pem");
+pub const TLS_KEY: &[u8] = include_bytes!("../fixtures/tls-key.pem");
+
+/// HTTPS server on `127.0.0.1` presenting [`TLS_CERT`] and answering every request with
+/// [`STS_IDENTITY_BODY`] over HTTP/1.1; it asks for a client certificate signed by
+/// [`TLS_CERT`] but does not require one.
+pub struct TlsServer {
+    uri: String,
+    handshakes: Arc<Mutex<Vec<Handshake>>>,
+}
+
+/// What a client sent in a handshake that was followed by a request.
+struct Handshake {
+    client_cert: bool,
+    alpn: Vec<String>,
+}
+
+impl TlsServer {
+    pub fn start() -> Self {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let cert = CertificateDer::from_pem_slice(TLS_CERT).unwrap();
+        let mut roots = rustls::RootCertStore::empty();
+        roots.add(cert.clone()).unwrap();
+        let verifier = rustls::server::WebPkiClientVerifier::builder_with_provider(
+            roots.into(),
+            provider.clone(),
+        )
+        .allow_unauthenticated()
+        .build()
+        .unwrap();
+        let mut config = rustls::ServerConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_client_cert_verifier(verifier)
+            .with_single_cert(vec![cert], PrivateKeyDer::from_pem_slice(TLS_KEY).unwrap())
+            .unwrap();
+        config.alpn_protocols = vec![b"http/1.1".to_vec()];
+        let config = Arc::new(config);
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let uri = format!("https://{}/", listener.local_addr().unwrap());
+        let handshakes = Arc::new(Mutex::new(Vec::new()));
+        let recorded = handshakes.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else { continue };
+                let (config, recorded) = (config.clone(), recorded.clone());
+                std::thread::spawn(move || {
+                    let _ = serve_tls(stream, config, &recorded);
+                });
+            }
+        });
+        Self { uri, handshakes }
+    }
+
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    /// Whether the client presented a certificate, for each completed handshake.
+    pub fn client_certs(&self) -> Vec<bool> {
+        let handshakes = self.handshakes.lock().unwrap();
+        handshakes.iter().map(|h| h.client_cert).collect()
+    }
+
+    /// The ALPN protocols the client offered, for each completed handshake.
+    pub fn offered_protocols(&self) -> Vec<Vec<String>> {
+        let handshakes = self.handshakes.lock().unwrap();
+        handshakes.iter().map(|h| h.alpn.clone()).collect()
+    }
+}
+
+fn serve_tls(
+    mut stream: TcpStream,
+    config: Arc<rustls::ServerConfig>,
+    handshakes: &Mutex<Vec<Handshake>>,
+) -> std::io::Result<()> {
+    let mut acceptor = rustls::server::Acceptor::default();
+    let accepted = loop {
+        if acceptor.read_tls(&mut stream)? == 0 {
+            return Ok(());
+        }
+        if let Some(accepted) = acceptor
+            .accept()
+            .map_err(|(e, _)| std::io::Error::other(e))?
+        {
+            break accepted;
+        }
+    };
+    let alpn = accepted
+        .client_hello()
+        .alpn()
+        .into_iter()
+        .flatten()
+        .map(|protocol| String::from_utf8_lossy(protocol).into_owned())
+        .collect();
+    let conn = accepted
+        .into_connection(config)
+        .map_err(|(e, _)| std::io::Error::other(e))?;
+    let mut tls = rustls::StreamOwned::new(conn, stream);
+
+    let mut request = Vec::new();
+    let mut buf = [0; 1024];
+    while !request.ends_with(b"\r\n\r\n") {
+        let n = tls.read(&mut buf)?;
+        if n == 0 {
+            return Ok(());
+        }
+        request.extend_from_slice(&buf[..n]);
+    }
+    handshakes.lock().unwrap().push(Handshake {
+        client_cert: tls.conn.peer_certificates().is_some(),
+        alpn,
+    });
+    write!(
+        tls,
+        "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{STS_IDENTITY_BODY}",
+        STS_IDENTITY_BODY.len()
+    )?;
+    tls.conn.send_close_notify();
+    tls.flush()
+}
+
+/// Minimal keep-alive HTTP server that answers every request with `body` and counts the
+/// connections it accepts.
+pub fn spawn_keep_alive_server(body: &'static str) -> (String, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = format!("http://{}/", listener.local_addr().unwrap());
+    let connections = Arc::new(AtomicUsize::new(0));
+    let accepted = connections.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { return };
+            accepted.fetch_add(1, Ordering::SeqCst);
+            std::thread::spawn(move || {
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                loop {
+                    let mut line = String::new();
+                    // Skip the request line and headers; the test only sends GETs.
+                    loop {
+                        line.clear();
+                        if reader.read_line(&mut line).unwrap_or(0) == 0 {
+                            return;
+                        }
+                        if line == "\r\n" {
+                            break;
+                        }
+                    }
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{body}",
+                        body.len()
+                    );
+                    if stream.write_all(response.as_bytes()).is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+    });
+    (endpoint, connections)
+}